@@ -0,0 +1,368 @@
+use std::{fs, io, path::Path};
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::grid::*;
+use crate::maze::*;
+use crate::sensor::*;
+
+// ARENA DEFAULTS
+const BOUNDARY_COLOR: Srgba = Srgba::rgb(0.6, 0.6, 0.6);
+const WALL_COLOR: Srgba = Srgba::rgb(0.85, 0.85, 0.85);
+const OUTLINE_SEGMENTS: usize = 64; // Straight pieces of a plotted circle
+const WALL_THICKNESS: f32 = 1.5; // Drawn only, walls have no thickness for movement and vision
+const MAPS_DIRECTORY: &str = "maps";
+const MAP_EXTENSIONS: [&str; 3] = ["map", "maze", "png"]; // Tried in order when a map is given by name
+const WALL_GRID_CELL: f32 = 30.0; // Movement and vision only test walls in the cells they cross
+const WALL_GRID_MARGIN: f32 = 0.5; // Walls are filed this far past their ends
+
+// Outline of the world, centred on the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaShape {
+    Circle(f32), // Radius
+    Rect(Vec2),  // Half size
+}
+
+impl ArenaShape {
+    pub fn bounds(&self) -> Rect {
+        match *self {
+            ArenaShape::Circle(radius) => Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(radius)),
+            ArenaShape::Rect(half_size) => Rect::from_center_half_size(Vec2::ZERO, half_size),
+        }
+    }
+}
+
+// What happens to a mouse that reaches the outline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    Wall,  // Stops at the edge, seen as a wall
+    Wrap,  // Comes back in on the opposite side, unseen
+    Death, // Terminated at the edge, seen as a wall
+}
+
+// Where a move ended and whether it crossed a deadly boundary
+pub struct Movement {
+    pub position: Vec2,
+    pub died: bool,
+}
+
+// Outline plus static wall segments that block movement and vision
+#[derive(Resource, Debug, Clone)]
+pub struct Arena {
+    pub shape: ArenaShape,
+    pub boundary: BoundaryMode,
+    walls: Vec<(Vec2, Vec2)>, // Added through add_wall so wall_grid knows them
+    wall_grid: SpatialGrid,
+    pub blocks: Vec<Rect>, // Solid areas drawn filled, their edges are in walls
+    pub food_areas: Vec<Rect>, // Food spawns in these when there are any
+    pub spawn: Vec2, // Where mice start every generation
+}
+
+impl Arena {
+    pub fn new(shape: ArenaShape, boundary: BoundaryMode) -> Self {
+        Self {
+            shape,
+            boundary,
+            walls: Vec::new(),
+            wall_grid: SpatialGrid::new(shape.bounds(), WALL_GRID_CELL, WALL_GRID_MARGIN),
+            blocks: Vec::new(),
+            food_areas: Vec::new(),
+            spawn: Vec2::ZERO,
+        }
+    }
+
+    pub fn walls(&self) -> &[(Vec2, Vec2)] {
+        &self.walls
+    }
+
+    pub fn add_wall(&mut self, start: Vec2, end: Vec2) {
+        self.wall_grid.insert_segment(self.walls.len(), start, end);
+        self.walls.push((start, end));
+    }
+
+    // Rebuilds the wall grid over the current bounds
+    fn index_walls(&mut self) {
+        self.wall_grid = SpatialGrid::new(self.bounds(), WALL_GRID_CELL, WALL_GRID_MARGIN);
+        for (index, &(start, end)) in self.walls.iter().enumerate() {
+            self.wall_grid.insert_segment(index, start, end);
+        }
+    }
+
+    // A path, or a name looked up in maps/ as .map, .maze or .png.
+    // Mazes are ASCII (.maze) or image (.png) grids, anything else is a map file.
+    pub fn load(name: &str, defaults: Arena) -> io::Result<Self> {
+        let path = if Path::new(name).exists() {
+            name.to_string()
+        } else {
+            MAP_EXTENSIONS
+                .iter()
+                .map(|extension| format!("{}/{}.{}", MAPS_DIRECTORY, name, extension))
+                .find(|path| Path::new(path).exists())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no map named {}", name)))?
+        };
+        match Path::new(&path).extension().and_then(|extension| extension.to_str()) {
+            Some("maze") => Ok(Maze::read_ascii(&path)?.arena()),
+            Some("png") => Ok(Maze::read_image(&path)?.arena()),
+            _ => Self::read_map(&path, defaults),
+        }
+    }
+
+    // Map file with one entry per line, # starts a comment:
+    //   shape circle <radius> | shape rect <half width> <half height>
+    //   boundary wall|wrap|death
+    //   wall <x1> <y1> <x2> <y2>
+    //   food <x1> <y1> <x2> <y2>
+    //   spawn <x> <y>
+    // Entries left out keep the values of `defaults`
+    fn read_map(path: &str, defaults: Arena) -> io::Result<Self> {
+        let mut arena = defaults;
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, message));
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let numbers = |words: &[&str]| -> io::Result<Vec<f32>> {
+                words
+                    .iter()
+                    .map(|word| word.parse().map_err(|error| invalid(&format!("{}", error))))
+                    .collect()
+            };
+            match words[..] {
+                ["shape", "circle", radius] => arena.shape = ArenaShape::Circle(numbers(&[radius])?[0]),
+                ["shape", "rect", width, height] => {
+                    let half = numbers(&[width, height])?;
+                    arena.shape = ArenaShape::Rect(Vec2::new(half[0], half[1]));
+                }
+                ["boundary", mode] => {
+                    arena.boundary = match mode {
+                        "wall" => BoundaryMode::Wall,
+                        "wrap" => BoundaryMode::Wrap,
+                        "death" => BoundaryMode::Death,
+                        _ => return Err(invalid("boundary must be wall, wrap or death")),
+                    }
+                }
+                ["wall", ..] if words.len() == 5 => {
+                    let ends = numbers(&words[1..])?;
+                    arena.add_wall(Vec2::new(ends[0], ends[1]), Vec2::new(ends[2], ends[3]));
+                }
+                ["food", ..] if words.len() == 5 => {
+                    let corners = numbers(&words[1..])?;
+                    arena.food_areas.push(Rect::new(corners[0], corners[1], corners[2], corners[3]));
+                }
+                ["spawn", x, y] => arena.spawn = Vec2::from_slice(&numbers(&[x, y])?),
+                _ => return Err(invalid("expected shape, boundary, wall, food or spawn")),
+            }
+        }
+        // The shape may have changed after walls were filed
+        arena.index_walls();
+        Ok(arena)
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.shape.bounds()
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        match self.shape {
+            ArenaShape::Circle(radius) => position.length() <= radius,
+            ArenaShape::Rect(half_size) => position.abs().cmple(half_size).all(),
+        }
+    }
+
+    // Moves are stopped by walls they would cross, then the boundary mode applies.
+    // A wrapped move is also stopped by walls on its way in from the opposite side.
+    pub fn constrain(&self, from: Vec2, to: Vec2) -> Movement {
+        if self.crosses_wall(from, to) {
+            return Movement { position: from, died: false };
+        }
+        if self.contains(to) {
+            return Movement { position: to, died: false };
+        }
+        match self.boundary {
+            BoundaryMode::Wall => Movement { position: self.clamp(to), died: false },
+            BoundaryMode::Death => Movement { position: self.clamp(to), died: true },
+            BoundaryMode::Wrap => {
+                let position = self.wrap(to);
+                if self.crosses_wall(self.entry(from, to, position), position) {
+                    return Movement { position: from, died: false };
+                }
+                Movement { position, died: false }
+            }
+        }
+    }
+
+    fn crosses_wall(&self, from: Vec2, to: Vec2) -> bool {
+        let step = to - from;
+        let length = step.length();
+        if length <= 0.0 {
+            return false;
+        }
+        let direction = step / length;
+        let mut blocked = false;
+        self.wall_grid.along(from, to, |index| {
+            let (start, end) = self.walls[index];
+            blocked = blocked || ray_segment(from, direction, start, end).is_some_and(|distance| distance <= length);
+        });
+        blocked
+    }
+
+    // Distance to the first wall, or to the outline when it can be seen, within range
+    pub fn ray(&self, origin: Vec2, direction: Vec2, range: f32) -> Option<f32> {
+        let mut nearest = match self.boundary {
+            BoundaryMode::Wrap => None,
+            BoundaryMode::Wall | BoundaryMode::Death => Some(self.exit(origin, direction)),
+        };
+        self.wall_grid.along(origin, origin + direction * range, |index| {
+            let (start, end) = self.walls[index];
+            if let Some(distance) = ray_segment(origin, direction, start, end) {
+                nearest = Some(nearest.map_or(distance, |nearest: f32| nearest.min(distance)));
+            }
+        });
+        nearest.filter(|&distance| distance <= range)
+    }
+
+    // Outline only, walls and blocks are meshes from arena_setup
+    pub fn draw(&self, gizmos: &mut Gizmos) {
+        match self.shape {
+            ArenaShape::Circle(radius) => {
+                gizmos.circle_2d(Vec2::ZERO, radius, BOUNDARY_COLOR);
+            }
+            ArenaShape::Rect(half_size) => {
+                gizmos.rect_2d(Vec2::ZERO, 0.0, half_size * 2.0, BOUNDARY_COLOR);
+            }
+        }
+    }
+
+    // Closed polyline around the arena, for plots
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self.shape {
+            ArenaShape::Circle(radius) => (0..=OUTLINE_SEGMENTS)
+                .map(|index| Vec2::from_angle(index as f32 / OUTLINE_SEGMENTS as f32 * std::f32::consts::TAU) * radius)
+                .collect(),
+            ArenaShape::Rect(half_size) => vec![
+                Vec2::new(-half_size.x, -half_size.y),
+                Vec2::new(half_size.x, -half_size.y),
+                Vec2::new(half_size.x, half_size.y),
+                Vec2::new(-half_size.x, half_size.y),
+                Vec2::new(-half_size.x, -half_size.y),
+            ],
+        }
+    }
+
+    fn clamp(&self, position: Vec2) -> Vec2 {
+        match self.shape {
+            ArenaShape::Circle(radius) => position.clamp_length_max(radius),
+            ArenaShape::Rect(half_size) => position.clamp(-half_size, half_size),
+        }
+    }
+
+    // Re-enters from the opposite side by as much as it overshot
+    fn wrap(&self, position: Vec2) -> Vec2 {
+        match self.shape {
+            ArenaShape::Circle(radius) => {
+                let overshoot = (position.length() - radius).min(radius);
+                -position.normalize_or_zero() * (radius - overshoot)
+            }
+            ArenaShape::Rect(half_size) => {
+                let size = half_size * 2.0;
+                (position + half_size).rem_euclid(size) - half_size
+            }
+        }
+    }
+
+    // Where a move from `from` to `to` that wrapped to `wrapped` comes back in
+    fn entry(&self, from: Vec2, to: Vec2, wrapped: Vec2) -> Vec2 {
+        match self.shape {
+            ArenaShape::Circle(radius) => -to.normalize_or_zero() * radius,
+            ArenaShape::Rect(_) => {
+                let direction = (to - from).normalize_or_zero();
+                from + direction * self.exit(from, direction) + (wrapped - to)
+            }
+        }
+    }
+
+    // Distance from a point inside to where the ray leaves the outline
+    fn exit(&self, origin: Vec2, direction: Vec2) -> f32 {
+        match self.shape {
+            ArenaShape::Circle(radius) => {
+                let along = origin.dot(direction);
+                let inside = radius * radius - origin.length_squared();
+                (-along + (along * along + inside).max(0.0).sqrt()).max(0.0)
+            }
+            ArenaShape::Rect(half_size) => {
+                let axis = |origin: f32, direction: f32, half: f32| {
+                    if direction > 0.0 {
+                        (half - origin) / direction
+                    } else if direction < 0.0 {
+                        (-half - origin) / direction
+                    } else {
+                        f32::INFINITY
+                    }
+                };
+                axis(origin.x, direction.x, half_size.x)
+                    .min(axis(origin.y, direction.y, half_size.y))
+                    .max(0.0)
+            }
+        }
+    }
+}
+
+// Spawns a mesh for every wall and block of the arena
+pub fn arena_setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+) {
+    let material = materials.add(Color::from(WALL_COLOR));
+    let mut spawn = |size: Vec2, transform: Transform| {
+        commands.spawn(MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle::from_size(size))),
+            material: material.clone(),
+            transform,
+            ..default()
+        });
+    };
+    for block in arena.blocks.iter() {
+        spawn(block.size(), Transform::from_translation(block.center().extend(-1.0)));
+    }
+    for &(start, end) in arena.walls().iter() {
+        let along = end - start;
+        let transform = Transform::from_translation(((start + end) / 2.0).extend(-1.0))
+            .with_rotation(Quat::from_rotation_z(along.y.atan2(along.x)));
+        spawn(Vec2::new(start.distance(end) + WALL_THICKNESS, WALL_THICKNESS), transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_stop_wrapped_moves_on_the_way_in() {
+        for shape in [ArenaShape::Rect(Vec2::splat(10.0)), ArenaShape::Circle(10.0)] {
+            let mut arena = Arena::new(shape, BoundaryMode::Wrap);
+            let from = Vec2::new(9.0, 0.0);
+            let to = Vec2::new(12.0, 0.0);
+            assert_eq!(arena.constrain(from, to).position, Vec2::new(-8.0, 0.0));
+            arena.add_wall(Vec2::new(-9.0, -5.0), Vec2::new(-9.0, 5.0));
+            assert_eq!(arena.constrain(from, to).position, from);
+        }
+    }
+
+    #[test]
+    fn walls_stop_moves_across_them() {
+        let mut arena = Arena::new(ArenaShape::Circle(100.0), BoundaryMode::Wall);
+        arena.add_wall(Vec2::new(5.0, -5.0), Vec2::new(5.0, 5.0));
+        assert_eq!(arena.constrain(Vec2::ZERO, Vec2::new(6.0, 0.0)).position, Vec2::ZERO);
+        assert_eq!(arena.constrain(Vec2::ZERO, Vec2::new(4.0, 0.0)).position, Vec2::new(4.0, 0.0));
+        assert_eq!(arena.ray(Vec2::ZERO, Vec2::X, 200.0), Some(5.0));
+        assert_eq!(arena.ray(Vec2::ZERO, -Vec2::X, 200.0), Some(100.0));
+        assert_eq!(arena.ray(Vec2::ZERO, -Vec2::X, 50.0), None);
+    }
+}
@@ -0,0 +1,221 @@
+use std::f32::consts::PI;
+
+use crate::controller::*;
+
+// Hand written cart-pole controllers used as a reference for the evolved brains.
+// They take the same normalized observation as the network
+// (angle, cart position, cart velocity, angular velocity) and return one action in [-1, 1].
+
+// LQR DEFAULTS
+const LQR_WEIGHTS: [f64; 4] = [0.01, 0.01, 10.0, 1.0]; // x, v, angle from upright, angular velocity
+const LQR_ACTION_WEIGHT: f64 = 1.0;
+const LQR_ITERATIONS: usize = 5000;
+// SWING UP DEFAULTS
+const SWING_GAIN: f32 = 100.0;
+const SWING_CENTERING: f32 = 0.5;
+const SWING_SWITCH_ANGLE: f32 = 45.0; // Degrees from upright where LQR takes over
+const VELOCITY_GAIN: f32 = 0.2;
+// PID DEFAULTS
+const PID_GAINS: [f32; 3] = [20.0, 1.0, 10.0]; // Proportional, integral, derivative on the angle
+const PID_VELOCITY: f32 = 0.05; // Damping on the cart velocity
+const PID_POSITION: f32 = 0.05; // Same sign as the LQR position gain, the cart has to lead the pole
+
+// Physical parameters and observation scales the controllers are built for
+#[derive(Debug, Clone, Copy)]
+pub struct CartPoleModel {
+    pub gravity: f32,
+    pub length: f32,
+    pub power: f32, // Cart acceleration for an action of 1.0
+    pub rail: f32,
+    pub velocity_range: f32,
+    pub angular_velocity_range: f32,
+    pub delta_time: f32, // Nominal step the controllers are tuned for
+}
+
+// State recovered from a normalized observation, phi is measured from upright
+#[derive(Debug, Clone, Copy)]
+struct CartPoleState {
+    x: f32,
+    v: f32,
+    angle: f32,
+    phi: f32,
+    omega: f32,
+}
+
+impl CartPoleModel {
+    fn state(&self, inputs: &[f32]) -> CartPoleState {
+        let angle = inputs[0] * PI;
+        let phi = if angle >= 0.0 { angle - PI } else { angle + PI };
+        CartPoleState {
+            x: inputs[1] * self.rail,
+            v: inputs[2] * self.velocity_range,
+            angle,
+            phi,
+            omega: inputs[3] * self.angular_velocity_range,
+        }
+    }
+    // Action that drives the cart velocity towards a target
+    fn track_velocity(&self, target: f32, v: f32) -> f32 {
+        (VELOCITY_GAIN * (target - v)).clamp(-1.0, 1.0)
+    }
+}
+
+// Linear quadratic regulator around the upright equilibrium
+#[derive(Debug, Clone)]
+pub struct Lqr {
+    model: CartPoleModel,
+    gain: [f32; 4],
+}
+
+impl Lqr {
+    pub fn new(model: CartPoleModel) -> Self {
+        Self {
+            model,
+            gain: lqr_gain(&model),
+        }
+    }
+    fn action(&self, state: &CartPoleState) -> f32 {
+        let s = [state.x, state.v, state.phi, state.omega];
+        let action: f32 = -(0..4).map(|i| self.gain[i] * s[i]).sum::<f32>();
+        action.clamp(-1.0, 1.0)
+    }
+}
+
+impl Controller for Lqr {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32> {
+        let state = self.model.state(observation);
+        vec![self.action(&state)]
+    }
+    fn name(&self) -> &'static str {
+        "LQR"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+// Energy pumping swing-up that hands over to LQR near upright
+#[derive(Debug, Clone)]
+pub struct SwingUp {
+    model: CartPoleModel,
+    lqr: Lqr,
+}
+
+impl SwingUp {
+    pub fn new(model: CartPoleModel) -> Self {
+        Self {
+            model,
+            lqr: Lqr::new(model),
+        }
+    }
+}
+
+impl Controller for SwingUp {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32> {
+        let state = self.model.state(observation);
+        if state.phi.abs() < SWING_SWITCH_ANGLE.to_radians() {
+            return vec![self.lqr.action(&state)];
+        }
+        // Energy per unit inertia, zero hanging down and 2g/L upright
+        let ratio = self.model.gravity / self.model.length;
+        let energy = 0.5 * state.omega * state.omega + ratio * (1.0 - state.angle.cos());
+        let target_energy = 2.0 * ratio;
+        let target = SWING_GAIN * (energy - target_energy) * state.omega * state.angle.cos()
+            - SWING_CENTERING * state.x;
+        let limit = self.model.velocity_range * 0.5;
+        vec![self.model.track_velocity(target.clamp(-limit, limit), state.v)]
+    }
+    fn name(&self) -> &'static str {
+        "Swing-up"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+// PID on the angle from upright with small cart terms
+#[derive(Debug, Clone)]
+pub struct Pid {
+    model: CartPoleModel,
+    integral: f32,
+}
+
+impl Pid {
+    pub fn new(model: CartPoleModel) -> Self {
+        Self {
+            model,
+            integral: 0.0,
+        }
+    }
+}
+
+impl Controller for Pid {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32> {
+        let state = self.model.state(observation);
+        let [kp, ki, kd] = PID_GAINS;
+        self.integral = (self.integral + state.phi * self.model.delta_time).clamp(-1.0, 1.0);
+        let action = -(kp * state.phi + ki * self.integral + kd * state.omega)
+            - PID_VELOCITY * state.v
+            + PID_POSITION * state.x;
+        vec![action.clamp(-1.0, 1.0)]
+    }
+    fn reset(&mut self) {
+        self.integral = 0.0;
+    }
+    fn name(&self) -> &'static str {
+        "PID"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+// Solves the discrete Riccati equation for the cart-pole linearized around upright.
+// x' = v, v' = power * u, phi' = omega, omega' = (g / L) * phi + v / L
+fn lqr_gain(model: &CartPoleModel) -> [f32; 4] {
+    let dt = model.delta_time as f64;
+    let g = model.gravity as f64;
+    let l = model.length as f64;
+    let mut a = [[0.0_f64; 4]; 4];
+    for (i, row) in a.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    a[0][1] = dt;
+    a[2][3] = dt;
+    a[3][2] = (g / l) * dt;
+    a[3][1] = dt / l;
+    let b = [0.0, model.power as f64 * dt, 0.0, 0.0];
+
+    let mut p = [[0.0_f64; 4]; 4];
+    for i in 0..4 {
+        p[i][i] = LQR_WEIGHTS[i];
+    }
+    let mut k = [0.0_f64; 4];
+    for _ in 0..LQR_ITERATIONS {
+        // pb = P * B, pa = P * A
+        let pb: Vec<f64> = (0..4).map(|i| (0..4).map(|j| p[i][j] * b[j]).sum()).collect();
+        let mut pa = [[0.0_f64; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                pa[i][j] = (0..4).map(|m| p[i][m] * a[m][j]).sum();
+            }
+        }
+        let denominator = LQR_ACTION_WEIGHT + (0..4).map(|i| b[i] * pb[i]).sum::<f64>();
+        // K = (R + B'PB)^-1 B'PA
+        for j in 0..4 {
+            k[j] = (0..4).map(|i| b[i] * pa[i][j]).sum::<f64>() / denominator;
+        }
+        // P = Q + A'PA - A'PB K
+        let mut next = [[0.0_f64; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                let apa: f64 = (0..4).map(|m| a[m][i] * pa[m][j]).sum();
+                let apb: f64 = (0..4).map(|m| a[m][i] * pb[m]).sum();
+                next[i][j] = apa - apb * k[j];
+            }
+            next[i][i] += LQR_WEIGHTS[i];
+        }
+        p = next;
+    }
+    [k[0] as f32, k[1] as f32, k[2] as f32, k[3] as f32]
+}
@@ -0,0 +1,188 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::controller::*;
+
+// CAMERA DEFAULTS
+const CAMERA_SCALE: f32 = 0.5; // World units per screen pixel until the first fit
+const MIN_SCALE: f32 = 0.05;
+const MAX_SCALE: f32 = 10.0;
+const ZOOM_STEP: f32 = 0.1; // Fraction of the scale per wheel line or key press
+const PAN_SPEED: f32 = 600.0; // Screen pixels per second for keyboard panning
+const FIT_MARGIN: f32 = 1.1;
+const PAN_BUTTONS: [MouseButton; 2] = [MouseButton::Right, MouseButton::Middle]; // Left click selects
+const ZOOM_IN: KeyCode = KeyCode::Equal;
+const ZOOM_OUT: KeyCode = KeyCode::Minus;
+const FIT_ALL: KeyCode = KeyCode::Home;
+const PAN_KEYS: [(KeyCode, Vec2); 4] = [
+    (KeyCode::ArrowLeft, Vec2::NEG_X),
+    (KeyCode::ArrowRight, Vec2::X),
+    (KeyCode::ArrowUp, Vec2::Y),
+    (KeyCode::ArrowDown, Vec2::NEG_Y),
+];
+
+// Wheel zooms toward the cursor, right or middle drag and the arrow keys pan,
+// +/- zoom around the centre and Home fits the bounds and focus
+pub struct CameraControlsPlugin;
+
+impl Plugin for CameraControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFocus>()
+            .add_systems(Startup, camera_setup)
+            .add_systems(Update, (camera_fit, camera_zoom, camera_pan).chain());
+    }
+}
+
+// Camera controls that also fit every individual of a scenario
+pub struct CameraPlugin<T: Individual>(PhantomData<T>);
+
+impl<T: Individual> Default for CameraPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Individual> Plugin for CameraPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(CameraControlsPlugin)
+            .add_systems(Update, camera_focus::<T>.before(camera_fit));
+    }
+}
+
+// World area the scenario wants in view, inserted by its setup
+#[derive(Resource)]
+pub struct CameraBounds(pub Rect);
+
+// Area covered by the individuals right now, fitted together with the bounds
+#[derive(Resource, Default)]
+pub struct CameraFocus(pub Option<Rect>);
+
+fn camera_setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle {
+        projection: OrthographicProjection {
+            scale: CAMERA_SCALE,
+            near: -1000.0,
+            far: 1000.0,
+            ..default()
+        },
+        ..default()
+    });
+}
+
+fn camera_focus<T: Individual>(
+    individuals: Query<&T>,
+    mut focus: ResMut<CameraFocus>,
+) {
+    focus.0 = individuals
+        .iter()
+        .map(|individual| individual.position())
+        .fold(None, |area: Option<Rect>, position| match area {
+            Some(area) => Some(area.union_point(position)),
+            None => Some(Rect::from_center_size(position, Vec2::ZERO)),
+        });
+}
+
+fn camera_fit(
+    mut fitted: Local<bool>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bounds: Option<Res<CameraBounds>>,
+    focus: Res<CameraFocus>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    if *fitted && !keyboard.just_pressed(FIT_ALL) {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let area = match (bounds.map(|bounds| bounds.0), focus.0) {
+        (Some(bounds), Some(focus)) => bounds.union(focus),
+        (Some(area), None) | (None, Some(area)) => area,
+        (None, None) => return,
+    };
+    let scale = (area.size() / window.size()).max_element() * FIT_MARGIN;
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation.x = area.center().x;
+        transform.translation.y = area.center().y;
+        projection.scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+    }
+    *fitted = true;
+}
+
+fn camera_zoom(
+    mut wheel: EventReader<MouseWheel>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let mut amount: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+    // Keys zoom around the centre of the window
+    let mut toward_cursor = amount != 0.0;
+    if keyboard.just_pressed(ZOOM_IN) {
+        amount += 1.0;
+        toward_cursor = false;
+    }
+    if keyboard.just_pressed(ZOOM_OUT) {
+        amount -= 1.0;
+        toward_cursor = false;
+    }
+    if amount == 0.0 {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    // Cursor offset from the window centre, y up like the world
+    let offset = window
+        .cursor_position()
+        .filter(|_| toward_cursor)
+        .map(|cursor| (cursor - window.size() / 2.0) * Vec2::new(1.0, -1.0))
+        .unwrap_or(Vec2::ZERO);
+
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        let scale = (projection.scale * (1.0 - ZOOM_STEP).powf(amount)).clamp(MIN_SCALE, MAX_SCALE);
+        // Keep the world point under the cursor where it is
+        let shift = offset * (projection.scale - scale);
+        transform.translation.x += shift.x;
+        transform.translation.y += shift.y;
+        projection.scale = scale;
+    }
+}
+
+fn camera_pan(
+    mut motion: EventReader<MouseMotion>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+) {
+    // Screen pixels, y up
+    let mut pan: Vec2 = motion.read().map(|event| event.delta).sum::<Vec2>() * Vec2::new(-1.0, 1.0);
+    if !mouse.any_pressed(PAN_BUTTONS) {
+        pan = Vec2::ZERO;
+    }
+    for (key, direction) in PAN_KEYS {
+        if keyboard.pressed(key) {
+            pan += direction * PAN_SPEED * time.delta_seconds();
+        }
+    }
+    if pan == Vec2::ZERO {
+        return;
+    }
+    for (mut transform, projection) in cameras.iter_mut() {
+        transform.translation.x += pan.x * projection.scale;
+        transform.translation.y += pan.y * projection.scale;
+    }
+}
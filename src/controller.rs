@@ -0,0 +1,96 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bevy::prelude::*;
+use rand::RngCore;
+
+// Anything that turns an observation into an action. Controllers with internal
+// state clear it in reset, evolvable ones are the only ones selected and mutated.
+// Mutation draws from the given generator so seeded runs repeat.
+pub trait Controller: Send + Sync {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32>;
+    fn reset(&mut self) {}
+    fn mutate(&mut self, _rate: f32, _rng: &mut dyn RngCore) {}
+    fn evolvable(&self) -> bool {
+        false
+    }
+    fn name(&self) -> &'static str;
+    fn clone_box(&self) -> Box<dyn Controller>;
+    // Layer activations for an observation, input first and output last.
+    // Backends that hide their hidden layers only return those two.
+    fn trace(&self, _observation: &[f32]) -> Option<Vec<Vec<f32>>> {
+        None
+    }
+    // Weights as [layer][to][from] when the backend exposes them
+    fn weights(&self) -> Option<Vec<Vec<Vec<f32>>>> {
+        None
+    }
+}
+
+// Simulated creature carrying a controller, lets tools work on either scenario
+pub trait Individual: Component {
+    fn brain(&self) -> &dyn Controller;
+    fn observation(&self) -> Vec<f32>;
+    fn layout(&self) -> Vec<usize>;
+    fn fitness(&self) -> f32;
+    fn position(&self) -> Vec2; // World position as rendered
+    fn describe(&self) -> String; // State variables for the inspector
+    fn lineage(&self) -> &Lineage;
+}
+
+static NEXT_LINEAGE_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Where an individual's brain came from
+#[derive(Debug, Clone)]
+pub struct Lineage {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub born: usize, // Epoch the brain was created in
+}
+
+impl Lineage {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_LINEAGE_ID.fetch_add(1, Ordering::Relaxed),
+            parent: None,
+            born: 0,
+        }
+    }
+    pub fn child(&self, epoch: usize) -> Self {
+        Self {
+            id: NEXT_LINEAGE_ID.fetch_add(1, Ordering::Relaxed),
+            parent: Some(self.id),
+            born: epoch,
+        }
+    }
+}
+
+// Brain of a body that environments step with actions from elsewhere, never asked to act
+#[derive(Debug, Clone)]
+pub struct Idle;
+
+impl Controller for Idle {
+    fn act(&mut self, _observation: &[f32]) -> Vec<f32> {
+        Vec::new()
+    }
+    fn name(&self) -> &'static str {
+        "Idle"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Controller> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl fmt::Debug for dyn Controller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Controller({})", self.name())
+    }
+}
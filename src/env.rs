@@ -0,0 +1,169 @@
+use std::{fs, io};
+
+use rayon::prelude::*;
+
+use crate::controller::*;
+use crate::metrics::*;
+use crate::remote::*;
+
+// Options shared by the headless trainers
+pub struct HeadlessConfig {
+    pub generations: usize,
+    pub remote: Option<RemoteLink>,
+    pub metrics: Option<MetricsSink>,
+    pub disturbances: Vec<Disturbance>,
+    pub plot: Option<String>, // .svg or .png for the champion's final episode
+    pub seed: u64,
+    pub parallel: bool,
+}
+
+// Scripted push applied when an episode reaches `time`, so robustness scores are reproducible.
+// Impulses are velocity changes: horizontal on the pendulum bob and on the cart.
+#[derive(Debug, Clone, Copy)]
+pub struct Disturbance {
+    pub time: f32,
+    pub bob: f32,
+    pub cart: f32,
+}
+
+// One disturbance per line as "time bob cart", # starts a comment
+pub fn load_disturbances(path: &str) -> io::Result<Vec<Disturbance>> {
+    let mut disturbances = Vec::new();
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let values: Vec<f32> = line
+            .split_whitespace()
+            .map(|value| value.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, error)))?;
+        let [time, bob, cart] = values[..] else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: expected time, bob and cart", number + 1),
+            ));
+        };
+        disturbances.push(Disturbance { time, bob, cart });
+    }
+    Ok(disturbances)
+}
+
+// Gym style interface over a simulation: reset returns the first observation,
+// step applies an action and returns (observation, reward, done).
+pub trait Environment {
+    fn reset(&mut self) -> Vec<f32>;
+    fn step(&mut self, action: &[f32]) -> (Vec<f32>, f32, bool);
+    fn time(&self) -> f32; // Seconds simulated since the last reset
+}
+
+// Runs one full episode and returns the total reward
+pub fn run_episode(env: &mut impl Environment, controller: &mut dyn Controller) -> f32 {
+    controller.reset();
+    let mut observation = env.reset();
+    let mut total = 0.0;
+    loop {
+        let action = controller.act(&observation);
+        let (next_observation, reward, done) = env.step(&action);
+        total += reward;
+        observation = next_observation;
+        if done {
+            return total;
+        }
+    }
+}
+
+// Runs one episode per brain and returns (fitness, simulated time) in brain order, on every
+// core unless parallel is off. make_env builds the environment of an individual from its
+// index, so with per-episode seeds the results don't depend on scheduling.
+pub fn evaluate<E: Environment>(
+    brains: &mut [Box<dyn Controller>],
+    parallel: bool,
+    make_env: impl Fn(usize) -> E + Sync,
+) -> Vec<(f32, f32)> {
+    let episode = |(index, brain): (usize, &mut Box<dyn Controller>)| {
+        let mut env = make_env(index);
+        let fitness = run_episode(&mut env, brain.as_mut());
+        (fitness, env.time())
+    };
+    if parallel {
+        brains.par_iter_mut().enumerate().map(episode).collect()
+    } else {
+        brains.iter_mut().enumerate().map(episode).collect()
+    }
+}
+
+// Mixes the run seed with the generation and individual (splitmix64) so every episode
+// draws from its own stream. The trainers seed brain initialisation and mutation from the
+// run seed itself, so together a seed fixes a whole run.
+pub fn episode_seed(seed: u64, epoch: usize, index: usize) -> u64 {
+    let mut value = seed ^ (epoch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (index as u64).rotate_left(32);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+    use crate::mice::*;
+    use crate::network::*;
+    use crate::pendulum::*;
+
+    // Same action whatever it observes
+    #[derive(Clone)]
+    struct Constant(Vec<f32>);
+
+    impl Controller for Constant {
+        fn act(&mut self, _observation: &[f32]) -> Vec<f32> {
+            self.0.clone()
+        }
+        fn name(&self) -> &'static str {
+            "Constant"
+        }
+        fn clone_box(&self) -> Box<dyn Controller> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn cart_pole_episodes_repeat() {
+        let episode = || {
+            let mut env = CartPoleEnv::new(1.0 / 60.0);
+            env.set_start_angle(3.0);
+            let reward = run_episode(&mut env, &mut Constant(vec![0.3]));
+            (reward, env.time())
+        };
+        let (reward, time) = episode();
+        assert!(time > 0.0);
+        assert_eq!((reward, time), episode());
+    }
+
+    #[test]
+    fn mice_episodes_repeat_for_a_seed() {
+        let episode = |seed: u64| {
+            let mut env = MiceEnv::new(1.0 / 60.0, seed, MiceSenses::default(), MiceFitness::default(), mice_arena());
+            let reward = run_episode(&mut env, &mut Constant(vec![1.0, 0.2]));
+            (reward, env.time())
+        };
+        assert_eq!(episode(7), episode(7));
+        assert_eq!(episode(1234), episode(1234));
+    }
+
+    #[test]
+    fn parallel_and_sequential_fitness_match() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut brains: Vec<Box<dyn Controller>> = (0..8)
+            .map(|_| Box::new(Mlp::new(&brain_layout(MiceSenses::default()), &mut rng)) as Box<dyn Controller>)
+            .collect();
+        let mut fitness = |parallel: bool| {
+            evaluate(&mut brains, parallel, |index| {
+                MiceEnv::new(1.0 / 60.0, episode_seed(11, 1, index), MiceSenses::default(), MiceFitness::default(), mice_arena())
+            })
+        };
+        assert_eq!(fitness(true), fitness(false));
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::math::{IVec2, Rect, Vec2};
+
+// Uniform grid over item indices, rebuilt every tick. Items are stored in every cell their
+// radius touches, so walking the cells a segment crosses finds everything it can hit.
+// Positions outside the bounds fall into the edge cells.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    origin: Vec2,
+    cell_size: f32,
+    size: IVec2, // Columns and rows
+    item_radius: f32,
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(bounds: Rect, cell_size: f32, item_radius: f32) -> Self {
+        let size = (bounds.size() / cell_size).ceil().as_ivec2().max(IVec2::ONE);
+        Self {
+            origin: bounds.min,
+            cell_size,
+            size,
+            item_radius,
+            cells: vec![Vec::new(); (size.x * size.y) as usize],
+        }
+    }
+
+    // Clears the cells but keeps their allocations
+    pub fn rebuild(&mut self, positions: impl IntoIterator<Item = Vec2>) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+        for (item, position) in positions.into_iter().enumerate() {
+            self.insert(item, position);
+        }
+    }
+
+    // Adds an item, a moved item keeps its old cells until the next rebuild so callers
+    // check the real position of what they get back
+    pub fn insert(&mut self, item: usize, position: Vec2) {
+        let min = self.cell(position - self.item_radius);
+        let max = self.cell(position + self.item_radius);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let index = self.index(IVec2::new(x, y));
+                self.cells[index].push(item);
+            }
+        }
+    }
+
+    // Adds a segment to every cell its bounding box touches, grown by the item radius so
+    // segments on a cell border are found from both sides
+    pub fn insert_segment(&mut self, item: usize, start: Vec2, end: Vec2) {
+        let min = self.cell(start.min(end) - self.item_radius);
+        let max = self.cell(start.max(end) + self.item_radius);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let index = self.index(IVec2::new(x, y));
+                self.cells[index].push(item);
+            }
+        }
+    }
+
+    // Items that may lie within radius of center, possibly more than once
+    pub fn near(&self, center: Vec2, radius: f32, mut visit: impl FnMut(usize)) {
+        let min = self.cell(center - radius);
+        let max = self.cell(center + radius);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells[self.index(IVec2::new(x, y))].iter().for_each(|&item| visit(item));
+            }
+        }
+    }
+
+    // Items in the cells the segment crosses, walked from start to end
+    pub fn along(&self, start: Vec2, end: Vec2, mut visit: impl FnMut(usize)) {
+        let from = (start - self.origin) / self.cell_size;
+        let delta = (end - self.origin) / self.cell_size - from;
+        let mut cell = from.floor().as_ivec2();
+        // Fraction of the segment to the next cell border and between borders, per axis
+        let border = |from: f32, cell: i32, delta: f32| {
+            if delta > 0.0 {
+                (cell as f32 + 1.0 - from) / delta
+            } else if delta < 0.0 {
+                (cell as f32 - from) / delta
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut next = Vec2::new(border(from.x, cell.x, delta.x), border(from.y, cell.y, delta.y));
+        let step = Vec2::new(1.0 / delta.x.abs(), 1.0 / delta.y.abs());
+        let direction = IVec2::new(delta.x.signum() as i32, delta.y.signum() as i32);
+
+        let mut previous = None;
+        loop {
+            // Clamping repeats edge cells once the segment leaves the grid
+            let index = self.index(cell.clamp(IVec2::ZERO, self.size - 1));
+            if previous != Some(index) {
+                self.cells[index].iter().for_each(|&item| visit(item));
+                previous = Some(index);
+            }
+            if next.min_element() > 1.0 {
+                return;
+            }
+            if next.x < next.y {
+                cell.x += direction.x;
+                next.x += step.x;
+            } else {
+                cell.y += direction.y;
+                next.y += step.y;
+            }
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, self.size - 1)
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const COLUMNS: i32 = 8;
+    const ROWS: i32 = 6;
+
+    // Whether the segment passes through the box, grown or shrunk by margin
+    fn crosses(start: Vec2, end: Vec2, min: Vec2, max: Vec2, margin: f32) -> bool {
+        let (mut enter, mut exit) = (0.0f32, 1.0f32);
+        for (from, to, low, high) in [(start.x, end.x, min.x, max.x), (start.y, end.y, min.y, max.y)] {
+            let (low, high) = (low - margin, high + margin);
+            if from == to {
+                if from <= low || from >= high {
+                    return false;
+                }
+            } else {
+                let (a, b) = ((low - from) / (to - from), (high - from) / (to - from));
+                enter = enter.max(a.min(b));
+                exit = exit.min(a.max(b));
+            }
+        }
+        enter < exit
+    }
+
+    // Scans cells well past the bounds and clamps them like the grid does
+    fn brute_force(start: Vec2, end: Vec2, margin: f32) -> HashSet<usize> {
+        let mut cells = HashSet::new();
+        for y in -ROWS..ROWS * 2 {
+            for x in -COLUMNS..COLUMNS * 2 {
+                let min = Vec2::new(x as f32, y as f32);
+                if crosses(start, end, min, min + 1.0, margin) {
+                    let cell = IVec2::new(x, y).clamp(IVec2::ZERO, IVec2::new(COLUMNS - 1, ROWS - 1));
+                    cells.insert((cell.y * COLUMNS + cell.x) as usize);
+                }
+            }
+        }
+        cells
+    }
+
+    // Every cell the segment runs through is visited and nothing it misses
+    fn check(start: Vec2, end: Vec2) {
+        let mut grid = SpatialGrid::new(Rect::new(0.0, 0.0, COLUMNS as f32, ROWS as f32), 1.0, 0.0);
+        grid.rebuild((0..ROWS).flat_map(|y| (0..COLUMNS).map(move |x| Vec2::new(x as f32 + 0.5, y as f32 + 0.5))));
+        let mut visited = HashSet::new();
+        grid.along(start, end, |item| {
+            visited.insert(item);
+        });
+        let crossed = brute_force(start, end, -1e-4);
+        let touched = brute_force(start, end, 1e-4);
+        assert!(crossed.is_subset(&visited), "{start} to {end} missed {:?}", crossed.difference(&visited));
+        assert!(visited.is_subset(&touched), "{start} to {end} visited {:?}", visited.difference(&touched));
+    }
+
+    #[test]
+    fn along_axis_aligned() {
+        check(Vec2::new(0.5, 2.5), Vec2::new(6.5, 2.5));
+        check(Vec2::new(3.5, 5.5), Vec2::new(3.5, 0.2));
+        check(Vec2::new(0.5, 0.5), Vec2::new(3.0, 0.5));
+    }
+
+    #[test]
+    fn along_diagonal() {
+        check(Vec2::new(0.3, 0.2), Vec2::new(7.6, 5.1));
+        check(Vec2::new(7.2, 0.4), Vec2::new(1.1, 5.7));
+        check(Vec2::new(5.9, 4.3), Vec2::new(5.1, 0.6));
+    }
+
+    #[test]
+    fn along_cell_corners() {
+        check(Vec2::new(1.0, 1.0), Vec2::new(4.0, 4.0));
+        check(Vec2::new(1.0, 3.0), Vec2::new(5.0, 1.0));
+        check(Vec2::new(6.0, 5.0), Vec2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn along_out_of_bounds() {
+        check(Vec2::new(-3.0, 2.5), Vec2::new(10.0, 4.5));
+        check(Vec2::new(4.5, -2.0), Vec2::new(4.5, 9.0));
+        check(Vec2::new(-2.0, -1.5), Vec2::new(9.5, 7.25));
+    }
+}
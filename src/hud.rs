@@ -0,0 +1,149 @@
+use bevy::{
+    color::palettes::css::{DARK_GREY, LIME, YELLOW},
+    prelude::*,
+};
+
+// HUD DEFAULTS
+const HUD_TOGGLE: KeyCode = KeyCode::KeyH;
+const HISTORY_LENGTH: usize = 100; // Generations kept in the chart before it scrolls
+const CHART_POSITION: Vec2 = Vec2::new(10.0, 110.0); // Top left corner in screen pixels
+const CHART_SIZE: Vec2 = Vec2::new(300.0, 120.0);
+const FONT_SIZE: f32 = 16.0;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudStats>()
+            .add_systems(Startup, hud_setup)
+            .add_systems(Update, (hud_toggle, hud_text, hud_chart));
+    }
+}
+
+// Filled in by the generation systems of whichever scenario is running
+#[derive(Resource)]
+pub struct HudStats {
+    pub epoch: usize,
+    pub progress: f32, // Fraction of the current generation's timer
+    pub best: f32,
+    pub average: f32,
+    history: Vec<(f32, f32)>,
+    visible: bool,
+}
+
+impl Default for HudStats {
+    fn default() -> Self {
+        Self {
+            epoch: 0,
+            progress: 0.0,
+            best: 0.0,
+            average: 0.0,
+            history: Vec::new(),
+            visible: true,
+        }
+    }
+}
+
+impl HudStats {
+    pub fn record(&mut self, best: f32, average: f32) {
+        self.best = best;
+        self.average = average;
+        self.history.push((best, average));
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.remove(0);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct HudText;
+
+fn hud_setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        HudText,
+    ));
+}
+
+fn hud_toggle(
+    mut stats: ResMut<HudStats>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<HudText>>,
+) {
+    if keyboard.just_pressed(HUD_TOGGLE) {
+        stats.visible = !stats.visible;
+        for mut visibility in query.iter_mut() {
+            *visibility = if stats.visible { Visibility::Inherited } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn hud_text(
+    stats: Res<HudStats>,
+    mut query: Query<&mut Text, With<HudText>>,
+) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Generation: {}\nProgress: {:.0}%\nBest: {:.2}\nAverage: {:.2}",
+            stats.epoch,
+            stats.progress * 100.0,
+            stats.best,
+            stats.average,
+        );
+    }
+}
+
+// Draws the fitness history with gizmos pinned to the screen through the camera
+fn hud_chart(
+    stats: Res<HudStats>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut gizmo: Gizmos,
+) {
+    if !stats.visible || stats.history.len() < 2 {
+        return;
+    }
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let to_world = |screen: Vec2| camera.viewport_to_world_2d(camera_transform, screen);
+
+    let low = stats.history.iter().map(|&(best, average)| best.min(average)).fold(f32::INFINITY, f32::min);
+    let high = stats.history.iter().map(|&(best, average)| best.max(average)).fold(f32::NEG_INFINITY, f32::max);
+    let range = (high - low).max(f32::EPSILON);
+    let step = CHART_SIZE.x / (HISTORY_LENGTH - 1) as f32;
+    let point = |index: usize, value: f32| {
+        let x = CHART_POSITION.x + index as f32 * step;
+        let y = CHART_POSITION.y + CHART_SIZE.y * (1.0 - (value - low) / range);
+        to_world(Vec2::new(x, y))
+    };
+
+    let corners = [
+        CHART_POSITION,
+        CHART_POSITION + Vec2::new(CHART_SIZE.x, 0.0),
+        CHART_POSITION + CHART_SIZE,
+        CHART_POSITION + Vec2::new(0.0, CHART_SIZE.y),
+        CHART_POSITION,
+    ];
+    gizmo.linestrip_2d(corners.iter().filter_map(|&corner| to_world(corner)), Color::from(DARK_GREY));
+    gizmo.linestrip_2d(
+        stats.history.iter().enumerate().filter_map(|(index, &(best, _))| point(index, best)),
+        Color::from(YELLOW),
+    );
+    gizmo.linestrip_2d(
+        stats.history.iter().enumerate().filter_map(|(index, &(_, average))| point(index, average)),
+        Color::from(LIME),
+    );
+}
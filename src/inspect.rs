@@ -0,0 +1,184 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+
+use crate::controller::*;
+use crate::hud::*;
+
+// INSPECTOR DEFAULTS
+const FOLLOW_TOGGLE: KeyCode = KeyCode::KeyF;
+const PICK_RADIUS: f32 = 10.0; // In world units
+const FONT_SIZE: f32 = 14.0;
+
+// Click an individual to select it, F makes the camera follow the selection
+pub struct InspectPlugin<T: Individual>(PhantomData<T>);
+
+impl<T: Individual> Default for InspectPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Individual> Plugin for InspectPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspector>()
+            .add_systems(Startup, inspect_setup)
+            .add_systems(
+                Update,
+                (
+                    (inspect_track::<T>, inspect_pick::<T>).chain(),
+                    inspect_panel::<T>,
+                    inspect_follow::<T>,
+                ),
+            );
+    }
+}
+
+// Marks the individual the tools are looking at
+#[derive(Component)]
+pub struct Selected;
+
+#[derive(Resource, Default)]
+pub struct Inspector {
+    lineage: Option<usize>, // Brain the selection was made on
+    follow: bool,
+}
+
+#[derive(Component)]
+pub struct InspectorText;
+
+fn inspect_setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        InspectorText,
+    ));
+}
+
+// Selection survives a generation only while the individual keeps its brain, i.e. as an elite
+fn inspect_track<T: Individual>(
+    mut commands: Commands,
+    mut inspector: ResMut<Inspector>,
+    selected: Query<(Entity, &T), With<Selected>>,
+) {
+    for (entity, individual) in selected.iter() {
+        if inspector.lineage != Some(individual.lineage().id) {
+            commands.entity(entity).remove::<Selected>();
+            inspector.lineage = None;
+        }
+    }
+}
+
+// Mouse and keyboard state with the camera that maps the cursor into the world
+#[derive(SystemParam)]
+struct Pointer<'w, 's> {
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl Pointer<'_, '_> {
+    // World position of a plain left click this frame, Shift + click is left to scenario
+    // tools such as disturbances
+    fn clicked(&self) -> Option<Vec2> {
+        if !self.mouse.just_pressed(MouseButton::Left) || self.keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            return None;
+        }
+        let (Ok(window), Ok((camera, camera_transform))) = (self.windows.get_single(), self.cameras.get_single()) else {
+            return None;
+        };
+        window
+            .cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    }
+}
+
+fn inspect_pick<T: Individual>(
+    mut commands: Commands,
+    mut inspector: ResMut<Inspector>,
+    pointer: Pointer,
+    individuals: Query<(Entity, &T)>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    let Some(cursor) = pointer.clicked() else {
+        return;
+    };
+
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    inspector.lineage = None;
+
+    let nearest = individuals
+        .iter()
+        .map(|(entity, individual)| (entity, individual, individual.position().distance(cursor)))
+        .filter(|(_, _, distance)| *distance < PICK_RADIUS)
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+    if let Some((entity, individual, _)) = nearest {
+        commands.entity(entity).insert(Selected);
+        inspector.lineage = Some(individual.lineage().id);
+    }
+}
+
+fn inspect_panel<T: Individual>(
+    stats: Res<HudStats>,
+    selected: Query<&T, With<Selected>>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<InspectorText>>,
+) {
+    let Ok((mut text, mut visibility)) = panel.get_single_mut() else {
+        return;
+    };
+    let Ok(individual) = selected.get_single() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let lineage = individual.lineage();
+    let parent = match lineage.parent {
+        Some(parent) => format!("#{}", parent),
+        None => "none".to_string(),
+    };
+    *visibility = Visibility::Inherited;
+    text.sections[0].value = format!(
+        "Fitness: {:.2}\n{}\nLineage: #{} from {}, born in generation {}, age {} generations",
+        individual.fitness(),
+        individual.describe(),
+        lineage.id,
+        parent,
+        lineage.born,
+        stats.epoch.saturating_sub(lineage.born),
+    );
+}
+
+fn inspect_follow<T: Individual>(
+    mut inspector: ResMut<Inspector>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    selected: Query<&T, With<Selected>>,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+) {
+    if keyboard.just_pressed(FOLLOW_TOGGLE) {
+        inspector.follow = !inspector.follow;
+    }
+    if !inspector.follow {
+        return;
+    }
+    if let Ok(individual) = selected.get_single() {
+        let position = individual.position();
+        for mut transform in cameras.iter_mut() {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
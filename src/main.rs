@@ -6,20 +6,159 @@ use mice::*;
 mod pendulum;
 use pendulum::*;
 
+mod arena;
+use arena::*;
+mod baseline;
+mod camera;
+use camera::*;
+mod controller;
+mod env;
+use env::*;
+mod grid;
+mod maze;
+mod network;
+mod sensor;
+mod remote;
+use remote::*;
+mod metrics;
+use metrics::*;
+mod plot;
+mod replay;
+use replay::*;
+mod hud;
+use hud::*;
+mod netview;
+use netview::*;
+mod inspect;
+use inspect::*;
+mod simulation;
+use simulation::*;
+
+const HEADLESS_GENERATIONS: usize = 100;
+
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        //.add_systems(Startup, mice_setup)
-        //.add_systems(Update, mice_collect)
-        //.add_systems(Update, mice_apply)
-        //.add_systems(Update, mice_generation)
-        .add_systems(Startup, pendulum_setup)
-        .add_systems(Update, camera_zoomies)
-        .add_systems(Update, update_pendulum)
-        .add_systems(Update, render_pendulum)
-        .add_systems(Update, pendulum_network)
-        .add_systems(Update, pendulum_generation)
-        .run();
+    // --mice switches scenario, --headless trains without a window,
+    // --remote <address> hands the population to an external controller,
+    // --loopback <address> runs a test client for --remote,
+    // --metrics <file.csv|file.jsonl> writes per-generation statistics,
+    // --disturbances <file> pushes the headless carts on a script for robustness scoring,
+    // --record <directory> saves a generation every --record-every <n>, --replay <file> plays one back,
+    // --plot <file.svg|file.png> plots the headless champion's final episode,
+    // --seed <n> repeats a headless run, --sequential evaluates on one thread,
+    // --senses <list> gives the mice proprioceptive inputs, e.g. heading,energy or all,
+    // --arena <map> loads the mice arena outline and walls, or a maze, by path or by name from maps/,
+    // --fitness food|survival|energy picks what the mice are selected on,
+    // --ecology runs the windowed mice without generations, mice breed and starve on their own
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(address) = arg_value::<String>(&args, "--loopback") {
+        if let Err(error) = loopback_client(&address) {
+            eprintln!("Loopback client: {}", error);
+        }
+        return;
+    }
+    if let Some(path) = arg_value::<String>(&args, "--replay") {
+        let recording = Recording::read(&path).expect("Failed to read recording");
+        App::new()
+            .add_plugins((DefaultPlugins, ReplayPlugin))
+            .insert_resource(Replay::new(recording))
+            .run();
+        return;
+    }
+    let mice = args.iter().any(|arg| arg == "--mice");
+    let remote = arg_value::<String>(&args, "--remote").map(|address| {
+        RemoteLink::listen(&address).expect("Failed to open remote controller socket")
+    });
+    let metrics = arg_value::<String>(&args, "--metrics").map(|path| {
+        MetricsSink::create(&path).expect("Failed to create metrics file")
+    });
+    let ecology = args.iter().any(|arg| arg == "--ecology");
+    let mut senses: MiceSenses = arg_value::<String>(&args, "--senses")
+        .map(|list| list.parse().expect("Invalid --senses list"))
+        .unwrap_or_default();
+    // The time sense counts down a generation, mice in an ecology never get one
+    if ecology && senses.time {
+        eprintln!("Ignoring the time sense, --ecology has no generations");
+        senses.time = false;
+    }
+    let objective: MiceFitness = arg_value::<String>(&args, "--fitness")
+        .map(|name| name.parse().expect("Invalid --fitness"))
+        .unwrap_or_default();
+    let arena = match arg_value::<String>(&args, "--arena") {
+        Some(path) => Arena::load(&path, mice_arena()).expect("Failed to read arena map"),
+        None => mice_arena(),
+    };
+    if args.iter().any(|arg| arg == "--headless") {
+        let config = HeadlessConfig {
+            generations: arg_value(&args, "--generations").unwrap_or(HEADLESS_GENERATIONS),
+            remote,
+            metrics,
+            disturbances: arg_value::<String>(&args, "--disturbances")
+                .map(|path| load_disturbances(&path).expect("Failed to read disturbance script"))
+                .unwrap_or_default(),
+            plot: arg_value(&args, "--plot"),
+            seed: arg_value(&args, "--seed").unwrap_or_else(rand::random),
+            parallel: !args.iter().any(|arg| arg == "--sequential"),
+        };
+        println!("Seed: {}", config.seed);
+        if mice {
+            mice_headless(config, senses, objective, arena);
+        } else {
+            pendulum_headless(config);
+        }
+        return;
+    }
+
+    let mut app = App::new();
+    app.add_plugins((DefaultPlugins, HudPlugin, SimulationPlugin));
+    if let Some(remote) = remote {
+        app.insert_resource(remote);
+    }
+    if let Some(metrics) = metrics {
+        app.insert_resource(metrics);
+    }
+    if let Some(directory) = arg_value::<String>(&args, "--record") {
+        let scenario = if mice { ReplayScenario::Mice } else { ReplayScenario::Pendulum };
+        let every = arg_value(&args, "--record-every").unwrap_or(RECORD_EVERY);
+        app.insert_resource(Recorder::create(&directory, scenario, every).expect("Failed to create recording directory"));
+    }
+    if mice {
+        app
+            .insert_resource(senses)
+            .insert_resource(objective)
+            .insert_resource(arena)
+            .add_plugins((
+                CameraPlugin::<Mice>::default(),
+                NetworkViewPlugin::<Mice>::default(),
+                InspectPlugin::<Mice>::default(),
+            ))
+            .add_systems(Startup, (arena_setup, mice_setup))
+            .add_systems(Update, mice_apply);
+        if ecology {
+            app.init_resource::<Ecology>()
+                .add_systems(SimulationTick, (mice_collect, mice_record, mice_ecology).chain());
+        } else {
+            app.add_systems(SimulationTick, (mice_collect, mice_record, mice_generation).chain());
+        }
+    } else {
+        app
+            .add_plugins((
+                CameraPlugin::<PendulumCart>::default(),
+                NetworkViewPlugin::<PendulumCart>::default(),
+                InspectPlugin::<PendulumCart>::default(),
+            ))
+            .add_systems(Startup, pendulum_setup)
+            .add_systems(SimulationTick, (pendulum_network, update_pendulum, pendulum_record, pendulum_generation).chain())
+            .add_systems(Update, (pendulum_disturb, render_pendulum));
+    }
+    app.run();
+}
+
+// Parses the value following a flag, e.g. --generations 50
+fn arg_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .and_then(|value| value.parse().ok())
 }
 
 //meshes: &mut ResMut<Assets<Mesh>>,
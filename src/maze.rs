@@ -0,0 +1,219 @@
+use std::{
+    fs::{self, File},
+    io,
+};
+
+use bevy::math::{Rect, Vec2};
+
+use crate::arena::*;
+
+// MAZE DEFAULTS
+const MAZE_CELL: f32 = 30.0; // World units per maze cell
+const FOOD_INSET: f32 = 4.0; // Keeps dead end food off the walls
+const WALL_LUMA: u32 = 128; // Darker image pixels are walls
+
+// Grid maze, row 0 at the top like the file it came from
+pub struct Maze {
+    width: usize,
+    height: usize,
+    walls: Vec<bool>,
+    spawn: Option<(usize, usize)>,
+}
+
+impl Maze {
+    // One row per line: # is a wall, S the spawn and anything else open.
+    // Short lines are padded with walls.
+    pub fn read_ascii(path: &str) -> io::Result<Self> {
+        Self::parse_ascii(&fs::read_to_string(path)?)
+    }
+
+    fn parse_ascii(text: &str) -> io::Result<Self> {
+        let rows: Vec<&str> = text.lines().filter(|line| !line.is_empty()).collect();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let mut maze = Maze {
+            width,
+            height: rows.len(),
+            walls: vec![true; width * rows.len()],
+            spawn: None,
+        };
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                maze.walls[y * width + x] = cell == '#';
+                if cell == 'S' {
+                    maze.spawn = Some((x, y));
+                }
+            }
+        }
+        maze.check()
+    }
+
+    // One cell per pixel, dark pixels are walls and transparent ones open
+    pub fn read_image(path: &str) -> io::Result<Self> {
+        let to_io = |error: png::DecodingError| io::Error::new(io::ErrorKind::InvalidData, error.to_string());
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(to_io)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(to_io)?;
+        let samples = info.color_type.samples();
+        let (width, height) = (info.width as usize, info.height as usize);
+        let walls = buffer[..info.buffer_size()]
+            .chunks(samples)
+            .map(|pixel| {
+                let (color, alpha) = match samples {
+                    1 => (&pixel[..1], 255),
+                    2 => (&pixel[..1], pixel[1]),
+                    3 => (&pixel[..3], 255),
+                    _ => (&pixel[..3], pixel[3]),
+                };
+                let luma = color.iter().map(|&value| value as u32).sum::<u32>() / color.len() as u32;
+                alpha > 0 && luma < WALL_LUMA
+            })
+            .collect();
+        Maze { width, height, walls, spawn: None }.check()
+    }
+
+    fn check(self) -> io::Result<Self> {
+        if !self.walls.contains(&false) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "maze has no open cells"));
+        }
+        Ok(self)
+    }
+
+    // Walls along every edge between a wall and an open cell, merged into straight runs.
+    // The outline is a solid rectangle around the grid, food goes in the dead ends.
+    pub fn arena(&self) -> Arena {
+        let half_size = Vec2::new(self.width as f32, self.height as f32) * MAZE_CELL / 2.0;
+        let mut arena = Arena::new(ArenaShape::Rect(half_size), BoundaryMode::Wall);
+
+        // Horizontal edges lie above row y, vertical edges left of column x
+        for y in 1..self.height {
+            self.runs(self.width, |x| self.wall(x, y - 1) != self.wall(x, y), |start, end| {
+                arena.add_wall(self.corner(start, y), self.corner(end, y));
+            });
+        }
+        for x in 1..self.width {
+            self.runs(self.height, |y| self.wall(x - 1, y) != self.wall(x, y), |start, end| {
+                arena.add_wall(self.corner(x, start), self.corner(x, end));
+            });
+        }
+        for y in 0..self.height {
+            self.runs(self.width, |x| self.wall(x, y), |start, end| {
+                arena.blocks.push(Rect::from_corners(self.corner(start, y), self.corner(end, y + 1)));
+            });
+        }
+
+        let open: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| !self.wall(x, y))
+            .collect();
+        let dead_ends: Vec<(usize, usize)> = open
+            .iter()
+            .copied()
+            .filter(|&(x, y)| self.open_neighbours(x, y) == 1)
+            .collect();
+        let food_cells = if dead_ends.is_empty() { &open } else { &dead_ends };
+        arena.food_areas = food_cells
+            .iter()
+            .map(|&(x, y)| {
+                let cell = Rect::from_corners(self.corner(x, y), self.corner(x + 1, y + 1));
+                cell.inflate(-FOOD_INSET)
+            })
+            .collect();
+
+        // Without an S the mice start in the open cell closest to the middle
+        let spawn = self.spawn.filter(|&(x, y)| !self.wall(x, y)).unwrap_or_else(|| {
+            *open
+                .iter()
+                .min_by(|a, b| self.center(a.0, a.1).length().partial_cmp(&self.center(b.0, b.1).length()).unwrap())
+                .unwrap()
+        });
+        arena.spawn = self.center(spawn.0, spawn.1);
+        arena
+    }
+
+    fn wall(&self, x: usize, y: usize) -> bool {
+        self.walls[y * self.width + x]
+    }
+
+    fn open_neighbours(&self, x: usize, y: usize) -> usize {
+        let mut open = 0;
+        if x > 0 && !self.wall(x - 1, y) {
+            open += 1;
+        }
+        if x + 1 < self.width && !self.wall(x + 1, y) {
+            open += 1;
+        }
+        if y > 0 && !self.wall(x, y - 1) {
+            open += 1;
+        }
+        if y + 1 < self.height && !self.wall(x, y + 1) {
+            open += 1;
+        }
+        open
+    }
+
+    // Calls found with the start and end of every run of consecutive indices where edge holds
+    fn runs(&self, count: usize, edge: impl Fn(usize) -> bool, mut found: impl FnMut(usize, usize)) {
+        let mut start = None;
+        for index in 0..=count {
+            match (start, index < count && edge(index)) {
+                (None, true) => start = Some(index),
+                (Some(run), false) => {
+                    found(run, index);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // World position of the top left corner of a cell, y up and the maze centred
+    fn corner(&self, x: usize, y: usize) -> Vec2 {
+        Vec2::new(
+            x as f32 * MAZE_CELL - self.width as f32 * MAZE_CELL / 2.0,
+            self.height as f32 * MAZE_CELL / 2.0 - y as f32 * MAZE_CELL,
+        )
+    }
+
+    fn center(&self, x: usize, y: usize) -> Vec2 {
+        self.corner(x, y) + Vec2::new(MAZE_CELL, -MAZE_CELL) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORRIDOR: &str = "#####\n#S..#\n#####\n";
+
+    #[test]
+    fn corridor_merges_runs_and_feeds_dead_ends() {
+        let arena = Maze::parse_ascii(CORRIDOR).unwrap().arena();
+        // One wall above and below the corridor, one at either end
+        assert_eq!(arena.walls().len(), 4);
+        assert!(arena.walls().contains(&(Vec2::new(-45.0, 15.0), Vec2::new(45.0, 15.0))));
+        assert_eq!(arena.blocks.len(), 4);
+        // Food only in the two ends, inset from the walls
+        assert_eq!(arena.food_areas.len(), 2);
+        assert!(arena.food_areas.iter().all(|area| area.size() == Vec2::splat(MAZE_CELL - 2.0 * FOOD_INSET)));
+        assert_eq!(arena.spawn, Vec2::new(-30.0, 0.0));
+    }
+
+    #[test]
+    fn spawn_falls_back_to_the_middle() {
+        let arena = Maze::parse_ascii(&CORRIDOR.replace('S', ".")).unwrap().arena();
+        assert_eq!(arena.spawn, Vec2::ZERO);
+        assert!(Maze::parse_ascii("###\n###\n").is_err());
+    }
+
+    #[test]
+    fn corridor_walls_block_movement_and_vision() {
+        let arena = Maze::parse_ascii(CORRIDOR).unwrap().arena();
+        let spawn = arena.spawn;
+        assert_eq!(arena.constrain(spawn, spawn + Vec2::new(0.0, 20.0)).position, spawn);
+        assert_eq!(arena.constrain(spawn, spawn + Vec2::new(10.0, 0.0)).position, spawn + Vec2::new(10.0, 0.0));
+        assert_eq!(arena.ray(Vec2::ZERO, Vec2::Y, 100.0), Some(15.0));
+        assert_eq!(arena.ray(Vec2::ZERO, -Vec2::X, 100.0), Some(45.0));
+    }
+}
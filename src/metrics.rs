@@ -0,0 +1,186 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    time::Instant,
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+// A line of a metrics file, CSV files start with the header of their first row
+pub trait MetricsRow: Serialize {
+    fn header() -> &'static str;
+    fn csv(&self) -> String;
+}
+
+// One row of per-generation statistics
+#[derive(Serialize, Debug, Clone)]
+pub struct GenerationMetrics {
+    pub epoch: usize,
+    pub min: f32,
+    pub median: f32,
+    pub mean: f32,
+    pub max: f32,
+    pub std_dev: f32,
+    pub mutation_rate: f32,
+    pub simulated_time: f32, // Seconds simulated since the start of the run
+    pub wall_time: f32,      // Real seconds since the start of the run
+}
+
+impl GenerationMetrics {
+    pub fn from_fitness(
+        epoch: usize,
+        fitness: &[f32],
+        mutation_rate: f32,
+        simulated_time: f32,
+        wall_time: f32,
+    ) -> Self {
+        let mut sorted = fitness.to_vec();
+        sorted.sort_by(f32::total_cmp); // NaN from a broken controller must not abort the run
+        let count = sorted.len().max(1) as f32;
+        let mean = sorted.iter().sum::<f32>() / count;
+        let variance = sorted.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / count;
+        let median = match sorted.len() {
+            0 => 0.0,
+            length if length % 2 == 0 => (sorted[length / 2 - 1] + sorted[length / 2]) / 2.0,
+            length => sorted[length / 2],
+        };
+        Self {
+            epoch,
+            min: sorted.first().copied().unwrap_or(0.0),
+            median,
+            mean,
+            max: sorted.last().copied().unwrap_or(0.0),
+            std_dev: variance.sqrt(),
+            mutation_rate,
+            simulated_time,
+            wall_time,
+        }
+    }
+}
+
+impl MetricsRow for GenerationMetrics {
+    fn header() -> &'static str {
+        "epoch,min,median,mean,max,std_dev,mutation_rate,simulated_time,wall_time"
+    }
+    fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.epoch,
+            self.min,
+            self.median,
+            self.mean,
+            self.max,
+            self.std_dev,
+            self.mutation_rate,
+            self.simulated_time,
+            self.wall_time
+        )
+    }
+}
+
+// One row per time window of an ecology run, where there are no generations
+#[derive(Serialize, Debug, Clone)]
+pub struct WindowMetrics {
+    pub window: usize,
+    pub population: usize,
+    pub births: usize, // During the window
+    pub deaths: usize,
+    pub mean_energy: f32,
+    pub max_energy: f32,
+    pub mean_age: f32,
+    pub max_generation: usize, // Longest line of descent alive
+    pub simulated_time: f32,
+    pub wall_time: f32,
+}
+
+impl MetricsRow for WindowMetrics {
+    fn header() -> &'static str {
+        "window,population,births,deaths,mean_energy,max_energy,mean_age,max_generation,simulated_time,wall_time"
+    }
+    fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.window,
+            self.population,
+            self.births,
+            self.deaths,
+            self.mean_energy,
+            self.max_energy,
+            self.mean_age,
+            self.max_generation,
+            self.simulated_time,
+            self.wall_time
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricsFormat {
+    Csv,
+    Jsonl,
+}
+
+// Writes one line per generation or window, the format follows the file extension
+#[derive(Resource)]
+pub struct MetricsSink {
+    writer: BufWriter<File>,
+    format: MetricsFormat,
+    started: Instant,
+    header: bool, // CSV header written
+}
+
+impl MetricsSink {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let format = if path.ends_with(".jsonl") || path.ends_with(".json") {
+            MetricsFormat::Jsonl
+        } else {
+            MetricsFormat::Csv
+        };
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            format,
+            started: Instant::now(),
+            header: false,
+        })
+    }
+    // Real seconds since the sink was created
+    pub fn wall_time(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+    pub fn record<R: MetricsRow>(&mut self, metrics: &R) {
+        if let Err(error) = self.write(metrics) {
+            eprintln!("Metrics: {}", error);
+        }
+    }
+    fn write<R: MetricsRow>(&mut self, metrics: &R) -> io::Result<()> {
+        match self.format {
+            MetricsFormat::Csv => {
+                if !self.header {
+                    writeln!(self.writer, "{}", R::header())?;
+                    self.header = true;
+                }
+                writeln!(self.writer, "{}", metrics.csv())?
+            }
+            MetricsFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, metrics)?;
+                self.writer.write_all(b"\n")?;
+            }
+        }
+        // Flush every row so runs can be followed while they train
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_survive_nan_fitness() {
+        let metrics = GenerationMetrics::from_fitness(0, &[3.0, f32::NAN, 1.0, 2.0], 0.1, 0.0, 0.0);
+        assert_eq!(metrics.min, 1.0);
+        assert_eq!(metrics.median, 2.5);
+        assert!(metrics.max.is_nan());
+    }
+}
@@ -1,45 +1,134 @@
-use std::{borrow::BorrowMut, f32::consts::PI};
+use std::{borrow::BorrowMut, f32::consts::PI, str::FromStr};
 
-use astoria_ml::*;
 use bevy::{
     asset::Assets,
     color::palettes::css::{DARK_ORANGE, GREY, LIGHT_GOLDENROD_YELLOW},
-    math::bounding::*,
+    ecs::system::SystemParam,
     prelude::*,
     sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
 };
 use rand::prelude::*;
 
-// CAMERA DEFAULTS
-const CAMERA_SCALE: f32 = 0.5;
+use crate::arena::*;
+use crate::camera::*;
+use crate::controller::*;
+use crate::env::*;
+use crate::grid::*;
+use crate::hud::*;
+use crate::metrics::*;
+use crate::network::*;
+use crate::plot::*;
+use crate::remote::*;
+use crate::replay::*;
+use crate::sensor::*;
+
 // MICE DEFUALTS
-const BRAIN: [usize; 7] = [11,28,32,24,16,8,2];
+const BRAIN_HIDDEN: [usize; 5] = [28,32,24,16,8]; // Input and output layers follow the senses and actions
 const VISION_RANGE: f32 = 100.0;
 const VISION_ANGLE: f32 = 50.0;
 const VISION_LINES: usize = 11;
+const VISION_FALLOFF: Falloff = Falloff::Linear; // Sight reading from 1 touching an object to 0 at VISION_RANGE
+const VISION_CLASSES: &[SightClass] = &[SightClass::Food, SightClass::Wall, SightClass::Mouse];
+const VISION_ENCODING: SightEncoding = SightEncoding::PerClass;
+const MICE_RADIUS: f32 = 1.5; // Size other mice are seen at
 const COLOR_DEFAULT: [f32; 3] = [1.0, 1.0, 1.0];
 const MICE_VELOCITY: f32 = 1.0;
 const MICE_ROTATION: f32 = 20.0;
+// ECOLOGY DEFAULTS
+// With --ecology there are no generations, mice split when well fed and die when terminated
+const REPRODUCE_ENERGY: f32 = 60.0; // Parent and offspring each get half
+const OFFSPRING_DISTANCE: f32 = 5.0;
+const MAX_POPULATION: usize = 400;
+const ECOLOGY_WINDOW: f32 = 10.0; // Seconds of simulated time per statistics row
+// METABOLISM DEFAULTS
+// Energy drains with living, moving and turning, cheese refills it and a mouse at 0 starves
+const ENERGY_START: f32 = 8.0; // Below one generation's basal cost, so survival needs food
+const ENERGY_MAX: f32 = 100.0;
+const FOOD_ENERGY: f32 = 10.0;
+const BASAL_COST: f32 = 1.0; // Per second
+const MOVE_COST: f32 = 0.05; // Per unit of distance
+const TURN_COST: f32 = 0.5; // Per radian
 // SIMULATION DEFAULTS
 const DEBUG: bool = false;
 const POLULATION: usize = 100;
 const MAP_SIZE: f32 = 700.0;
-const MIN_RADIUS: f32 = 100.0;
-const MUTATION: f32 = 0.1;
+const ARENA_BOUNDARY: BoundaryMode = BoundaryMode::Wall; // Arena without a --arena map is a MAP_SIZE circle
+const MUTATION: f32 = 0.1; // Fraction of each layer's initial weight range
 const SIMULATION_TIME: f32 = 10.0;
+const GRID_CELL: f32 = 20.0; // Spatial grid cell size for vision and eating, at least twice FOOD_RADIUS
 // FOOD DEFAULTS
 const FOOD_COUNT: usize = 1000;
 const FOOD_RADIUS: f32 = 2.0;
+const FOOD_CLEARING: f32 = 50.0; // No food this close to the mice spawn
+const FOOD_ATTEMPTS: usize = 100; // Spawn points tried before food falls back to the mice spawn
+// HEADLESS DEFAULTS
+const HEADLESS_DELTA_TIME: f32 = 1.0 / 60.0;
+// TERMINATION DEFAULTS
+// Terminated mice are frozen and stop collecting food until the next generation
+const TERMINATE_STILL_TIME: Option<f32> = Some(2.0); // Seconds without moving
+const STILL_DISTANCE: f32 = 0.05; // Per frame movement below this counts as standing still
 
 #[derive(Resource)]
 pub struct Generation {
     epoch: usize,
-    max_fitness: usize,
+    max_fitness: f32,
+    simulated_time: f32,
 }
 
 #[derive(Resource)]
 pub struct GenerationTimer(Timer);
 
+// Births and deaths of the current statistics window of an ecology run
+#[derive(Resource, Default)]
+pub struct Ecology {
+    window_time: f32,
+    births: usize,
+    deaths: usize,
+    founder: Option<Founder>, // Kept across windows, extinctions reseed from it
+}
+
+impl Ecology {
+    // Keeps the brain of the longest-lived evolvable mouse to die so far
+    fn remember(&mut self, mice: &Mice) {
+        if mice.brain.evolvable() && self.founder.as_ref().is_none_or(|founder| mice.age > founder.age) {
+            self.founder = Some(Founder {
+                age: mice.age,
+                brain: mice.brain.clone(),
+                lineage: mice.lineage.clone(),
+                descent: mice.descent,
+            });
+        }
+    }
+}
+
+struct Founder {
+    age: f32,
+    brain: Box<dyn Controller>,
+    lineage: Lineage,
+    descent: usize,
+}
+
+// Everything mice_ecology needs to put new mice into the world
+#[derive(SystemParam)]
+pub struct MiceSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    mesh: Res<'w, MiceMesh>,
+    arena: Res<'w, Arena>,
+    senses: Option<Res<'w, MiceSenses>>,
+    objective: Option<Res<'w, MiceFitness>>,
+    remote: Option<Res<'w, RemoteLink>>,
+}
+
+impl MiceSpawner<'_, '_> {
+    fn spawn(&mut self, mice: Mice) {
+        spawn_mice(&mut self.commands, &self.mesh, &mut self.materials, mice);
+    }
+}
+
+#[derive(Resource)]
+pub struct MiceMesh(Mesh2dHandle);
+
 #[derive(Component)]
 pub struct Cheese; 
 
@@ -48,85 +137,336 @@ pub struct Mice {
     position: Vec3,
     direction: Quat, 
     sight: Vec<f32>,
-    fitness: usize,
+    eaten: usize,
     color: [f32; 3],
-    brain: Network,
+    brain: Box<dyn Controller>,
+    terminated: bool,
+    still_time: f32,
+    lineage: Lineage,
+    senses: MiceSenses,
+    action: [f32; 2], // Last brain outputs, move and turn
+    age: f32, // Seconds into the generation
+    energy: f32,
+    gathered: f32, // Energy from all cheese eaten this generation
+    objective: MiceFitness,
+    descent: usize, // Ancestors born in an ecology run
+    reach: Vec2, // Half size of the arena, for the position sense
+}
+
+// Optional inputs about the mouse itself, fed to the brain after its sight.
+// Chosen per run with --senses, e.g. --senses heading,energy or --senses all
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct MiceSenses {
+    pub action: bool, // Last move and turn
+    pub heading: bool, // Sine and cosine of the heading
+    pub energy: bool, // Energy as a fraction of ENERGY_MAX
+    pub position: bool, // Position with the arena edges at -1 and 1
+    pub time: bool, // Fraction of the generation left
+}
+
+impl MiceSenses {
+    pub fn inputs(&self) -> usize {
+        2 * self.action as usize
+            + 2 * self.heading as usize
+            + self.energy as usize
+            + 2 * self.position as usize
+            + self.time as usize
+    }
+}
+
+impl FromStr for MiceSenses {
+    type Err = String;
+
+    // Comma separated names, "all" turns every sense on and "none" leaves them off
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        let mut senses = MiceSenses::default();
+        for name in list.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "action" => senses.action = true,
+                "heading" => senses.heading = true,
+                "energy" => senses.energy = true,
+                "position" => senses.position = true,
+                "time" => senses.time = true,
+                "all" => {
+                    senses = MiceSenses { action: true, heading: true, energy: true, position: true, time: true }
+                }
+                "none" => {}
+                _ => return Err(format!("unknown sense {}, expected action, heading, energy, position, time, all or none", name)),
+            }
+        }
+        Ok(senses)
+    }
 }
 
+// What a generation rewards, chosen per run with --fitness
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MiceFitness {
+    #[default]
+    Food, // Cheese eaten
+    Survival, // Seconds alive
+    Energy, // Energy gathered from cheese
+}
 
+impl FromStr for MiceFitness {
+    type Err = String;
 
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "food" => Ok(MiceFitness::Food),
+            "survival" => Ok(MiceFitness::Survival),
+            "energy" => Ok(MiceFitness::Energy),
+            _ => Err(format!("unknown fitness {}, expected food, survival or energy", name)),
+        }
+    }
+}
 
 impl Default for Mice {
     fn default() -> Self {
+        let senses = MiceSenses::default();
+        Mice::new(senses, MiceFitness::default(), mice_brain(senses, &mut rand::thread_rng()))
+    }
+}
+
+impl Mice {
+    fn new(senses: MiceSenses, objective: MiceFitness, brain: Box<dyn Controller>) -> Self {
         let mut rnd = rand::thread_rng();
         let mice_positon = Vec3::new(0.0, 0.0, 1.0);
         let mice_direction = Quat::from_rotation_z(rnd.gen_range(0.0..360.0_f32).to_radians());
         Mice {
             position: mice_positon,
             direction: mice_direction,
-            sight: vec![0.0; VISION_LINES],
-            fitness: 0,
+            sight: vec![0.0; sight().channels() * VISION_LINES],
+            eaten: 0,
             color: COLOR_DEFAULT,
-            brain: Network::new(BRAIN.to_vec(), ActivationFunction::ReLU, ActivationFunction::Tanh)
+            brain,
+            terminated: false,
+            still_time: 0.0,
+            lineage: Lineage::new(),
+            senses,
+            action: [0.0; 2],
+            age: 0.0,
+            energy: ENERGY_START,
+            gathered: 0.0,
+            objective,
+            descent: 0,
+            reach: Vec2::splat(MAP_SIZE / 2.0),
+        }
+    }
+
+    fn reset(&mut self, rng: &mut impl Rng, arena: &Arena) {
+        self.position = arena.spawn.extend(1.0);
+        self.reach = arena.bounds().half_size();
+        self.direction = Quat::from_rotation_z(rng.gen_range(0.0..360.0_f32).to_radians());
+        self.sight = vec![0.0; sight().channels() * VISION_LINES];
+        self.eaten = 0;
+        self.terminated = false;
+        self.still_time = 0.0;
+        self.action = [0.0; 2];
+        self.age = 0.0;
+        self.energy = ENERGY_START;
+        self.gathered = 0.0;
+        self.brain.reset();
+    }
+
+    // Mutated copy placed next to the parent, the parent's energy is shared with it
+    fn offspring(&mut self, epoch: usize, arena: &Arena, rng: &mut impl Rng) -> Mice {
+        let mut brain = self.brain.clone();
+        brain.mutate(MUTATION, rng);
+        let mut child = Mice::new(self.senses, self.objective, brain);
+        child.reset(rng, arena);
+        let offset = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI)) * OFFSPRING_DISTANCE;
+        let position = self.position.truncate();
+        child.position = arena.constrain(position, position + offset).position.extend(self.position.z);
+        child.lineage = self.lineage.child(epoch);
+        child.descent = self.descent + 1;
+        self.energy /= 2.0;
+        child.energy = self.energy;
+        child
+    }
+
+    // Enabled proprioceptive readings, in the order of the MiceSenses fields
+    fn proprioception(&self) -> Vec<f32> {
+        let mut inputs = Vec::with_capacity(self.senses.inputs());
+        if self.senses.action {
+            inputs.extend(self.action);
+        }
+        if self.senses.heading {
+            let heading = self.direction.to_euler(EulerRot::XYZ).2;
+            inputs.extend([heading.sin(), heading.cos()]);
+        }
+        if self.senses.energy {
+            inputs.push(self.energy / ENERGY_MAX);
+        }
+        if self.senses.position {
+            inputs.extend((self.position.truncate() / self.reach).to_array());
+        }
+        if self.senses.time {
+            inputs.push((1.0 - self.age / SIMULATION_TIME).clamp(0.0, 1.0));
+        }
+        inputs
+    }
+
+    // Brain inputs, sight then proprioception
+    fn inputs(&self) -> Vec<f32> {
+        let mut inputs = self.sight.clone();
+        inputs.extend(self.proprioception());
+        inputs
+    }
+}
+
+impl Individual for Mice {
+    fn brain(&self) -> &dyn Controller {
+        self.brain.as_ref()
+    }
+    fn observation(&self) -> Vec<f32> {
+        self.inputs()
+    }
+    fn layout(&self) -> Vec<usize> {
+        if self.brain.evolvable() {
+            brain_layout(self.senses)
+        } else {
+            vec![input_count(self.senses), 2]
         }
     }
+    fn fitness(&self) -> f32 {
+        match self.objective {
+            MiceFitness::Food => self.eaten as f32,
+            MiceFitness::Survival => self.age,
+            MiceFitness::Energy => self.gathered,
+        }
+    }
+    fn position(&self) -> Vec2 {
+        self.position.truncate()
+    }
+    fn describe(&self) -> String {
+        let sight: Vec<String> = self.sight.iter().map(|value| format!("{:.2}", value)).collect();
+        let proprioception: Vec<String> = self.proprioception().iter().map(|value| format!("{:.2}", value)).collect();
+        format!(
+            "Brain: {}\nPosition: ({:.0}, {:.0})\nHeading: {:.0}\nEnergy: {:.1}\nEaten: {}\nSight: [{}]\nSelf: [{}]\nTerminated: {}",
+            self.brain.name(),
+            self.position.x,
+            self.position.y,
+            self.direction.to_euler(EulerRot::XYZ).2.to_degrees(),
+            self.energy,
+            self.eaten,
+            sight.join(", "),
+            proprioception.join(", "),
+            self.terminated,
+        )
+    }
+    fn lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+}
+
+// Gym style foraging episode for a single mouse that runs without Bevy
+pub struct MiceEnv {
+    mice: Mice,
+    food: Vec<Vec3>,
+    grid: SpatialGrid,
+    arena: Arena,
+    rng: StdRng, // Start heading and food placement
+    delta_time: f32,
+    time: f32,
+}
+
+impl MiceEnv {
+    pub fn new(delta_time: f32, seed: u64, senses: MiceSenses, objective: MiceFitness, arena: Arena) -> Self {
+        Self {
+            mice: Mice::new(senses, objective, Box::new(Idle)),
+            food: Vec::new(),
+            grid: food_grid(&arena),
+            arena,
+            rng: StdRng::seed_from_u64(seed),
+            delta_time,
+            time: 0.0,
+        }
+    }
+}
+
+impl MiceEnv {
+    // A mouse alone in its world only sees food
+    fn surroundings(&self) -> Surroundings<'_> {
+        Surroundings {
+            food: &self.food,
+            food_grid: &self.grid,
+            mice: &[],
+            mice_grid: None,
+            arena: &self.arena,
+        }
+    }
+}
+
+impl Environment for MiceEnv {
+    fn reset(&mut self) -> Vec<f32> {
+        self.mice.reset(&mut self.rng, &self.arena);
+        self.food = (0..FOOD_COUNT).map(|_| new_food_pos(&mut self.rng, &self.arena)).collect();
+        self.grid.rebuild(self.food.iter().map(|food| food.truncate()));
+        self.time = 0.0;
+        self.mice.sight = mice_vision(&self.mice, None, &self.surroundings());
+        self.mice.inputs()
+    }
+    fn step(&mut self, action: &[f32]) -> (Vec<f32>, f32, bool) {
+        let fitness = self.mice.fitness();
+        mice_update(&mut self.mice, action, &self.arena, self.delta_time);
+        mice_eat(&mut self.mice, &mut self.food, &mut self.grid, &mut self.rng, &self.arena);
+        self.time += self.delta_time;
+        self.grid.rebuild(self.food.iter().map(|food| food.truncate()));
+        self.mice.sight = mice_vision(&self.mice, None, &self.surroundings());
+        let done = self.mice.terminated || self.time >= SIMULATION_TIME;
+        (self.mice.inputs(), self.mice.fitness() - fitness, done)
+    }
+    fn time(&self) -> f32 {
+        self.time
+    }
 }
 
 pub fn mice_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    remote: Option<Res<RemoteLink>>,
+    senses: Option<Res<MiceSenses>>,
+    objective: Option<Res<MiceFitness>>,
+    arena: Res<Arena>,
 ) {
+    let senses = senses.map(|senses| *senses).unwrap_or_default();
+    let objective = objective.map(|objective| *objective).unwrap_or_default();
     commands.insert_resource(Generation{
         epoch: 0,
-        max_fitness: 0,
+        max_fitness: 0.0,
+        simulated_time: 0.0,
     });
     commands.insert_resource(GenerationTimer(Timer::from_seconds(SIMULATION_TIME, TimerMode::Repeating)));
-    commands.spawn(Camera2dBundle {
-        projection: OrthographicProjection {
-            scale: CAMERA_SCALE, // Zoom out (values less than 1.0 zoom out, values greater than 1.0 zoom in)
-            near: -1000.0, // Ensure it encompasses your z-range
-            far: 1000.0,   // Ensure it encompasses your z-range
-            
-            ..Default::default()
-        },
-        ..Default::default()
-    });
-    let mice_mesh: Mesh2dHandle = meshes
+    commands.insert_resource(CameraBounds(arena.bounds()));
+    let mice_mesh = MiceMesh(meshes
         .add(Triangle2d::new(
             Vec2::new(-1.0, 0.0),
             Vec2::new(1.0, 0.0),
             Vec2::new(0.0, 3.0),
-    )).into();
+    )).into());
+    let mut rng = rand::thread_rng();
     for i in 0..POLULATION {
-        commands.spawn((MaterialMesh2dBundle {
-            mesh: mice_mesh.clone(),
-            material: materials.add(Color::srgb_from_array(COLOR_DEFAULT)),
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
-            ..default()
-        },
-        Mice::default()));
+        let brain = match &remote {
+            Some(remote) => remote.controller(i, 2),
+            None => mice_brain(senses, &mut rng),
+        };
+        let mut mice = Mice::new(senses, objective, brain);
+        mice.reset(&mut rng, &arena);
+        spawn_mice(&mut commands, &mice_mesh, &mut materials, mice);
     }
+    commands.insert_resource(mice_mesh);
     let cheese_mesh: Mesh2dHandle = meshes
         .add(Circle {
             radius: FOOD_RADIUS,
             ..Default::default()
         }).into();
-    for i in 0..FOOD_COUNT {
-        let mut rng = rand::thread_rng();
-        
-        let angle = rng.gen_range(0.0..360.0_f32).to_radians();
-        
-        let radius = rng.gen_range((MIN_RADIUS/2.0)..(MAP_SIZE/2.0) as f32);
-        
-        let x = radius * angle.cos();
-        let y = radius * angle.sin();
-        
+    for _ in 0..FOOD_COUNT {
         commands.spawn((
             MaterialMesh2dBundle {
                 mesh: cheese_mesh.clone(),
                 material: materials.add(Color::Srgba(DARK_ORANGE.into())),
-                transform: Transform::from_translation(Vec3::new(x, y, 0.0)),
+                transform: Transform::from_translation(new_food_pos(&mut rng, &arena)),
                 ..default()
             },
             Cheese,
@@ -135,44 +475,134 @@ pub fn mice_setup(
     
 }
 
+fn spawn_mice(
+    commands: &mut Commands,
+    mesh: &MiceMesh,
+    materials: &mut Assets<ColorMaterial>,
+    mice: Mice,
+) {
+    commands.spawn((MaterialMesh2dBundle {
+        mesh: mesh.0.clone(),
+        material: materials.add(Color::srgb_from_array(mice.color)),
+        transform: Transform::from_translation(mice.position),
+        ..default()
+    },
+    mice));
+}
+
+// Food spawns in the arena's food areas, or anywhere open in the arena outside a clearing
+// around the mice spawn
 fn new_food_pos(
+    rng: &mut impl Rng,
+    arena: &Arena,
 ) -> Vec3{
-    let mut rng = rand::thread_rng();
-    let angle = rng.gen_range(0.0..360.0_f32).to_radians();
-    
-    let radius = rng.gen_range((MIN_RADIUS/2.0)..(MAP_SIZE/2.0) as f32);
-    
-    let x = radius * angle.cos();
-    let y = radius * angle.sin();
-    let new_pos = Vec3::new(x, y, 0.0);
-    new_pos
+    if let Some(area) = arena.food_areas.choose(rng) {
+        let x = rng.gen_range(area.min.x..=area.max.x);
+        let y = rng.gen_range(area.min.y..=area.max.y);
+        return Vec3::new(x, y, 0.0);
+    }
+    let bounds = arena.bounds();
+    for _ in 0..FOOD_ATTEMPTS {
+        let new_pos = Vec2::new(
+            rng.gen_range(bounds.min.x..=bounds.max.x),
+            rng.gen_range(bounds.min.y..=bounds.max.y),
+        );
+        let open = arena.contains(new_pos) && !arena.blocks.iter().any(|block| block.contains(new_pos));
+        if open && new_pos.distance(arena.spawn) >= FOOD_CLEARING {
+            return new_pos.extend(0.0);
+        }
+    }
+    arena.spawn.extend(0.0)
+}
+
+// Default arena, replaced by --arena <map>
+pub fn mice_arena() -> Arena {
+    Arena::new(ArenaShape::Circle(MAP_SIZE / 2.0), ARENA_BOUNDARY)
 }
 
 // Collect changes to the mice
 pub fn mice_collect(
-    mut mice: Query<&mut Mice>,
+    mut mice: Query<(Entity, &mut Mice)>,
     mut food_query: Query<&mut Transform, With<Cheese>>,
-    mut gizmo: Gizmos,
+    mut grid: Local<Option<SpatialGrid>>,
+    mut mice_grid: Local<Option<SpatialGrid>>,
+    arena: Res<Arena>,
+    time: Res<Time>,
 ) {
-    for mut mice in mice.iter_mut() {
-        mice.sight = mice_vision(&mut mice, &food_query, &mut gizmo);
-        let neura_outputs = mice_neura(&mice);
-        mice.position = neura_outputs.0;
-        mice.direction = neura_outputs.1;
-        for mut transform in food_query.iter_mut() {
-            let food_output = food_move(&mut mice, &mut transform);
-            mice.fitness = food_output.0;
-            transform.translation = food_output.1;
+    let mut food: Vec<Vec3> = food_query.iter().map(|transform| transform.translation).collect();
+    let grid = grid.get_or_insert_with(|| food_grid(&arena));
+    grid.rebuild(food.iter().map(|food| food.truncate()));
+    let positions: Vec<(Entity, Vec2)> = mice.iter().map(|(entity, mice)| (entity, mice.position.truncate())).collect();
+    let mice_grid = mice_grid.get_or_insert_with(|| SpatialGrid::new(arena.bounds(), GRID_CELL, MICE_RADIUS));
+    mice_grid.rebuild(positions.iter().map(|&(_, position)| position));
+    let delta_time = time.delta_seconds();
+    // Seeing, thinking and moving only read the world, so every mouse runs in parallel
+    let surroundings = Surroundings {
+        food: &food,
+        food_grid: grid,
+        mice: &positions,
+        mice_grid: Some(&*mice_grid),
+        arena: &arena,
+    };
+    mice.par_iter_mut().for_each(|(entity, mut mice)| {
+        if mice.terminated {
+            return;
+        }
+        mice.sight = mice_vision(&mice, Some(entity), &surroundings);
+        let outputs = mice_neura(&mut mice);
+        mice_update(&mut mice, &outputs, &arena, delta_time);
+    });
+    // Eating changes the food, so it runs in query order whatever the thread count
+    let mut rng = rand::thread_rng();
+    for (_, mut mice) in mice.iter_mut() {
+        if !mice.terminated {
+            mice_eat(&mut mice, &mut food, grid, &mut rng, &arena);
         }
     }
+    for (mut transform, food_position) in food_query.iter_mut().zip(food) {
+        transform.translation = food_position;
+    }
+}
+
+// Hands the state of every mouse and the food to the recorder when --record is given
+pub fn mice_record(
+    query: Query<&Mice>,
+    food_query: Query<&Transform, With<Cheese>>,
+    generation: Res<Generation>,
+    recorder: Option<ResMut<Recorder>>,
+    time: Res<Time>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let poses = query
+        .iter()
+        .map(|mice| Pose {
+            id: mice.lineage.id,
+            body: mice.position.truncate(),
+            head: (mice.position + mice.direction * Vec3::Y * 3.0).truncate(),
+            fitness: mice.fitness(),
+            baseline: false,
+        })
+        .collect();
+    let food: Vec<Vec2> = food_query.iter().map(|transform| transform.translation.truncate()).collect();
+    recorder.record(generation.epoch, time.delta_seconds(), poses, &food);
 }
 
 // Apply changes to the mice
 pub fn mice_apply(
     mut mouse_query: Query<(&mut Mice, &mut Transform, &mut Handle<ColorMaterial>), With<Mice>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    arena: Res<Arena>,
+    mut gizmo: Gizmos,
 ) {
+    arena.draw(&mut gizmo);
     for (mut mice, mut transform, mut color) in mouse_query.iter_mut() {
+        if DEBUG && !mice.terminated {
+            for (ray_start, ray_end) in vision_rays(&mice) {
+                gizmo.line_2d(ray_start, ray_end, Color::from(GREY));
+            }
+        }
         let material = materials.get_mut(color.id()).unwrap();
         material.color = Color::srgb_from_array(mice.color);
         transform.translation = mice.position;
@@ -180,75 +610,98 @@ pub fn mice_apply(
     }
 }
 
-pub fn camera_zoom(
-    mut query: Query<&mut OrthographicProjection, With<Camera>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-) {
-    for mut projection in query.iter_mut() {
-        if keyboard.just_pressed(KeyCode::Equal) {
-            projection.scale += 0.1;
-        }
-        if keyboard.just_pressed(KeyCode::Minus) {
-            projection.scale -= 0.1;
-        }
-    }
-}
-
-fn mice_vision(
-  mice: &mut Mice,
-  food_query: &Query<&mut Transform, With<Cheese>>,
-  gizmo: &mut Gizmos,
-) -> Vec<f32>{
-    let angles = (0..VISION_LINES)
-        .map(|i| {
-            (-VISION_ANGLE / 2.0 + i as f32 * VISION_ANGLE / (VISION_LINES - 1) as f32).to_radians()
-        }).collect::<Vec<f32>>();
-
+// Start and end point of every vision line
+fn vision_rays(mice: &Mice) -> Vec<(Vec2, Vec2)> {
     let mice_rotation = mice.direction.to_euler(EulerRot::XYZ).2;
     let ray_start = mice.position.xy();
-    let sight_distances: Vec<f32> = angles
-        .iter()
-        .map(|&angle| {
+    (0..VISION_LINES)
+        .map(|i| {
+            let angle = (-VISION_ANGLE / 2.0 + i as f32 * VISION_ANGLE / (VISION_LINES - 1) as f32).to_radians();
             let ray_direction =
                 Vec2::new(-(mice_rotation + angle).sin(), (mice_rotation + angle).cos());
-            let ray_end = ray_start + ray_direction * VISION_RANGE;
-            if DEBUG {gizmo.line_2d(ray_start, ray_end, Color::from(GREY));}
-            food_query
-                .iter()
-                .filter_map(|transform| {
-                    ray_intersects_aabb(
-                        ray_start,
-                        ray_end,
-                        Aabb2d::new(transform.translation.truncate(), Vec2::new(FOOD_RADIUS, FOOD_RADIUS)),
-                    )
-                }).min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(VISION_RANGE)
-        }) .collect();
-    
-    let sight_output: Vec<f32> = sight_distances
-        .iter()
-        .map(|&d| (d.clamp(0.0, 1.0) - 1.0).abs().sqrt())
-        .collect();
-    sight_output
+            (ray_start, ray_start + ray_direction * VISION_RANGE)
+        }).collect()
+}
+
+fn sight() -> Sight {
+    Sight {
+        sensor: RaySensor { range: VISION_RANGE, falloff: VISION_FALLOFF },
+        classes: VISION_CLASSES,
+        encoding: VISION_ENCODING,
+    }
+}
+
+fn input_count(senses: MiceSenses) -> usize {
+    sight().channels() * VISION_LINES + senses.inputs()
 }
 
-fn ray_intersects_aabb(ray_start: Vec2, ray_end: Vec2, aabb: Aabb2d) -> Option<f32> {
-    let ray_dir = ray_end - ray_start;
-    let inv_dir = Vec2::new(1.0 / ray_dir.x, 1.0 / ray_dir.y);
-    let (min, max) = (aabb.min, aabb.max);
+// Input layer sized from the senses, then the hidden layers and the two actions
+pub fn brain_layout(senses: MiceSenses) -> Vec<usize> {
+    let mut layout = vec![input_count(senses)];
+    layout.extend(BRAIN_HIDDEN);
+    layout.push(2);
+    layout
+}
 
-    let t1 = (min.x - ray_start.x) * inv_dir.x;
-    let t2 = (max.x - ray_start.x) * inv_dir.x;
-    let t3 = (min.y - ray_start.y) * inv_dir.y;
-    let t4 = (max.y - ray_start.y) * inv_dir.y;
+// Fresh random network sized for the senses
+fn mice_brain(senses: MiceSenses, rng: &mut impl Rng) -> Box<dyn Controller> {
+    Box::new(Mlp::new(&brain_layout(senses), rng))
+}
 
-    let tmin = t1.min(t2).max(t3.min(t4));
-    let tmax = t1.max(t2).min(t3.max(t4));
+// What a mouse can see this tick. Other mice are (entity, position), a mouse skips itself.
+struct Surroundings<'a> {
+    food: &'a [Vec3],
+    food_grid: &'a SpatialGrid,
+    mice: &'a [(Entity, Vec2)],
+    mice_grid: Option<&'a SpatialGrid>,
+    arena: &'a Arena,
+}
 
-    if tmax < 0.0 || tmin > tmax {
-        None
-    } else {
-        Some(tmin.max(0.0))
+// Every ray reports the nearest hit of each class, only objects in the grid cells it crosses
+// are tested. Walls hide whatever is behind them. There are no predators yet, their channel stays empty.
+fn mice_vision(
+  mice: &Mice,
+  own: Option<Entity>,
+  surroundings: &Surroundings,
+) -> Vec<f32>{
+    let sight = sight();
+    let mut inputs = Vec::with_capacity(sight.channels() * VISION_LINES);
+    for (ray_start, ray_end) in vision_rays(mice) {
+        let direction = (ray_end - ray_start) / VISION_RANGE;
+        let wall = surroundings.arena.ray(ray_start, direction, VISION_RANGE);
+        let nearest: Vec<Option<f32>> = sight
+            .classes
+            .iter()
+            .map(|class| {
+                let mut nearest: Option<f32> = None;
+                let mut hit = |center: Vec2, radius: f32| {
+                    if let Some(distance) = ray_circle(ray_start, direction, center, radius) {
+                        nearest = Some(nearest.map_or(distance, |nearest| nearest.min(distance)));
+                    }
+                };
+                match class {
+                    SightClass::Food => surroundings.food_grid.along(ray_start, ray_end, |index| {
+                        hit(surroundings.food[index].truncate(), FOOD_RADIUS);
+                    }),
+                    SightClass::Mouse => {
+                        if let Some(mice_grid) = surroundings.mice_grid {
+                            mice_grid.along(ray_start, ray_end, |index| {
+                                let (entity, position) = surroundings.mice[index];
+                                if Some(entity) != own {
+                                    hit(position, MICE_RADIUS);
+                                }
+                            });
+                        }
+                    }
+                    SightClass::Wall => nearest = wall,
+                    SightClass::Predator => {}
+                }
+                nearest.filter(|&distance| wall.is_none_or(|wall| distance <= wall))
+            })
+            .collect();
+        sight.encode(&nearest, &mut inputs);
     }
+    inputs
 }
 
 fn mice_move(input: f32, mice: &Mice) -> Vec3 {
@@ -265,55 +718,368 @@ fn mice_turn(input: f32, mice: &Mice) -> Quat{
 }  
 
 fn mice_neura(
-    mice: &Mice,
-) -> (Vec3, Quat ) {
-    let inputs = mice.sight.clone();
-    let outputs = mice.brain.forward(inputs);
+    mice: &mut Mice,
+) -> Vec<f32> {
+    let inputs = mice.inputs();
+    mice.brain.act(&inputs)
+}
+
+// Acts on the brain outputs inside the arena, walls stop the move, a deadly edge or an
+// empty energy budget terminates
+fn mice_update(
+    mice: &mut Mice,
+    outputs: &[f32],
+    arena: &Arena,
+    delta_time: f32,
+) {
+    let (position, direction) = mice_act(mice, outputs);
+    let movement = arena.constrain(mice.position.truncate(), position.truncate());
+    let position = movement.position.extend(position.z);
+    let moved = mice.position.distance(position);
+    let turned = (MICE_ROTATION.to_radians() * outputs[1]).abs();
+    mice_metabolise(mice, moved, turned, delta_time);
+    mice_check_termination(mice, position, delta_time);
+    if movement.died || mice.energy <= 0.0 {
+        mice.terminated = true;
+    }
+    mice.position = position;
+    mice.direction = direction;
+    mice_age(mice, delta_time);
+}
+
+// Also remembers the action for the action sense
+fn mice_act(
+    mice: &mut Mice,
+    outputs: &[f32],
+) -> (Vec3, Quat) {
+    mice.action = [outputs[0], outputs[1]];
     let movement = mice_move(outputs[0], mice); 
     let direction = mice_turn(outputs[1], mice);
     (movement, direction)
 }
 
-fn food_move(
+// Only the distance actually covered costs energy, pushing against a wall is free
+fn mice_metabolise(mice: &mut Mice, moved: f32, turned: f32, delta_time: f32) {
+    let cost = BASAL_COST * delta_time + MOVE_COST * moved + TURN_COST * turned;
+    mice.energy = (mice.energy - cost).max(0.0);
+}
+
+fn mice_age(mice: &mut Mice, delta_time: f32) {
+    mice.age += delta_time;
+}
+
+fn mice_check_termination(
+    mice: &mut Mice,
+    new_position: Vec3,
+    delta_time: f32,
+) {
+    if mice.position.distance(new_position) < STILL_DISTANCE {
+        mice.still_time += delta_time;
+    } else {
+        mice.still_time = 0.0;
+    }
+    if let Some(still_time) = TERMINATE_STILL_TIME {
+        if mice.still_time >= still_time {
+            mice.terminated = true;
+        }
+    }
+}
+
+// Eaten food respawns elsewhere and goes into the grid at its new position
+fn mice_eat(
     mice: &mut Mice,
-    food_transform: &mut Transform,
-) -> (usize, Vec3) {
-    let mut mice_fitness = mice.fitness;
-    let mut food_position = food_transform.translation;
-    if mice.position.distance(food_transform.translation) < FOOD_RADIUS {
-        mice_fitness += 1;
-        food_position = new_food_pos();
+    food: &mut [Vec3],
+    grid: &mut SpatialGrid,
+    rng: &mut impl Rng,
+    arena: &Arena,
+) {
+    let mut eaten = Vec::new();
+    grid.near(mice.position.truncate(), FOOD_RADIUS, |index| {
+        if mice.position.distance(food[index]) < FOOD_RADIUS {
+            eaten.push(index);
+        }
+    });
+    eaten.sort_unstable();
+    eaten.dedup();
+    for index in eaten {
+        mice.eaten += 1;
+        mice.energy = (mice.energy + FOOD_ENERGY).min(ENERGY_MAX);
+        mice.gathered += FOOD_ENERGY;
+        food[index] = new_food_pos(rng, arena);
+        grid.insert(index, food[index].truncate());
     }
-    (mice_fitness, food_position)
+}
+
+fn food_grid(arena: &Arena) -> SpatialGrid {
+    SpatialGrid::new(arena.bounds(), GRID_CELL, FOOD_RADIUS)
 }
 
 pub fn mice_generation(
     mut query: Query<(&mut Mice, &mut Transform, Entity), With<Mice>>,
     mut generation: ResMut<Generation>,
     mut gen_timer: ResMut<GenerationTimer>,
+    mut metrics: Option<ResMut<MetricsSink>>,
+    mut hud: ResMut<HudStats>,
+    arena: Res<Arena>,
     time: ResMut<Time>,
 ) {
-    if gen_timer.0.tick(time.delta()).just_finished() {
+    generation.simulated_time += time.delta_seconds();
+    // End the generation early once every mouse has been terminated
+    let all_terminated = query.iter().all(|(mice, _, _)| mice.terminated);
+    if gen_timer.0.tick(time.delta()).just_finished() || all_terminated {
+        gen_timer.0.reset();
         let mut average = 0.0;
         for (mice,_,_) in query.iter() {
-            average += mice.fitness as f32;
+            average += mice.fitness();
         }
         let mean = average / 100.0 as f32;
         generation.epoch += 1;
+        if let Some(metrics) = metrics.as_mut() {
+            let fitness: Vec<f32> = query.iter().map(|(mice, _, _)| mice.fitness()).collect();
+            let wall_time = metrics.wall_time();
+            metrics.record(&GenerationMetrics::from_fitness(
+                generation.epoch,
+                &fitness,
+                MUTATION,
+                generation.simulated_time,
+                wall_time,
+            ));
+        }
     
-        if let Some((best_mice, _, _)) = query.iter().max_by_key(|(mice, _,_)| mice.fitness) {
+        if let Some((best_mice, _, _)) = query.iter().max_by(|(a, _, _), (b, _, _)| a.fitness().total_cmp(&b.fitness())) {
             let best_brain = best_mice.brain.clone();
+            let best_lineage = best_mice.lineage.clone();
         
-            println!("{} *** Fitness: {} Mean: {}", generation.epoch, best_mice.fitness, mean);
+            println!("{} *** Fitness: {} Mean: {}", generation.epoch, best_mice.fitness(), mean);
+            generation.max_fitness = best_mice.fitness();
+            hud.record(best_mice.fitness(), mean);
+            let mut rng = rand::thread_rng();
             for (mut mice, _, _) in query.iter_mut() {
+                mice.reset(&mut rng, &arena);
+                // Remote controllers keep their agent
+                if !mice.brain.evolvable() {
+                    continue;
+                }
                 let mut new_brain = best_brain.clone();
-                new_brain.mutate(MUTATION);
-                mice.position = Mice::default().position;
-                mice.direction = Mice::default().direction;
-                mice.fitness = Mice::default().fitness;
+                new_brain.mutate(MUTATION, &mut rng);
                 mice.brain = new_brain;
+                mice.lineage = best_lineage.child(generation.epoch);
             }
         }
     }
-    
-}
\ No newline at end of file
+    hud.epoch = generation.epoch;
+    hud.progress = gen_timer.0.fraction();
+}
+
+// Open-ended alternative to mice_generation: mice with enough energy split into a mutated
+// offspring, terminated ones die, and food supply keeps the population in check.
+// Statistics are logged per ECOLOGY_WINDOW instead of per generation.
+pub fn mice_ecology(
+    mut spawner: MiceSpawner,
+    mut query: Query<(Entity, &mut Mice)>,
+    mut ecology: ResMut<Ecology>,
+    mut generation: ResMut<Generation>,
+    mut metrics: Option<ResMut<MetricsSink>>,
+    mut hud: ResMut<HudStats>,
+    time: Res<Time>,
+) {
+    generation.simulated_time += time.delta_seconds();
+    ecology.window_time += time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    let mut population = query.iter().filter(|(_, mice)| !mice.terminated).count();
+    for (entity, mut mice) in query.iter_mut() {
+        if mice.terminated {
+            ecology.remember(&mice);
+            spawner.commands.entity(entity).despawn();
+            ecology.deaths += 1;
+            continue;
+        }
+        // Remote controllers can't be copied into offspring
+        if mice.energy < REPRODUCE_ENERGY || !mice.brain.evolvable() || population >= MAX_POPULATION {
+            continue;
+        }
+        let child = mice.offspring(generation.epoch, &spawner.arena, &mut rng);
+        spawner.spawn(child);
+        ecology.births += 1;
+        population += 1;
+    }
+    // Starts over from the longest-lived mouse so far, or the remote agents again, after an
+    // extinction. The first copy of the founder is unchanged and the rest are its mutations.
+    if population == 0 {
+        println!("{} *** Extinct, reseeding {} mice", generation.epoch, POLULATION);
+        let senses = spawner.senses.as_deref().copied().unwrap_or_default();
+        let objective = spawner.objective.as_deref().copied().unwrap_or_default();
+        for agent in 0..POLULATION {
+            let mut mice = match (&spawner.remote, &ecology.founder) {
+                (Some(remote), _) => Mice::new(senses, objective, remote.controller(agent, 2)),
+                (None, Some(founder)) => {
+                    let mut brain = founder.brain.clone();
+                    if agent > 0 {
+                        brain.mutate(MUTATION, &mut rng);
+                    }
+                    let mut mice = Mice::new(senses, objective, brain);
+                    mice.lineage = founder.lineage.child(generation.epoch);
+                    mice.descent = founder.descent + 1;
+                    mice
+                }
+                (None, None) => Mice::new(senses, objective, mice_brain(senses, &mut rng)),
+            };
+            mice.reset(&mut rng, &spawner.arena);
+            spawner.spawn(mice);
+        }
+    }
+
+    if ecology.window_time >= ECOLOGY_WINDOW {
+        // Offspring from this tick are counted in the next window
+        let alive: Vec<&Mice> = query.iter().map(|(_, mice)| mice).filter(|mice| !mice.terminated).collect();
+        let count = alive.len().max(1) as f32;
+        let mean_energy = alive.iter().map(|mice| mice.energy).sum::<f32>() / count;
+        let max_energy = alive.iter().map(|mice| mice.energy).fold(0.0, f32::max);
+        let mean_age = alive.iter().map(|mice| mice.age).sum::<f32>() / count;
+        let max_generation = alive.iter().map(|mice| mice.descent).max().unwrap_or(0);
+        generation.epoch += 1;
+        println!(
+            "{} *** Population: {} Births: {} Deaths: {} Energy: {:.1} Age: {:.1} Descent: {}",
+            generation.epoch, alive.len(), ecology.births, ecology.deaths, mean_energy, mean_age, max_generation
+        );
+        if let Some(metrics) = metrics.as_mut() {
+            let wall_time = metrics.wall_time();
+            metrics.record(&WindowMetrics {
+                window: generation.epoch,
+                population: alive.len(),
+                births: ecology.births,
+                deaths: ecology.deaths,
+                mean_energy,
+                max_energy,
+                mean_age,
+                max_generation,
+                simulated_time: generation.simulated_time,
+                wall_time,
+            });
+        }
+        hud.record(max_energy, mean_energy);
+        *ecology = Ecology {
+            founder: ecology.founder.take(),
+            ..default()
+        };
+    }
+    hud.epoch = generation.epoch;
+    hud.progress = ecology.window_time / ECOLOGY_WINDOW;
+}
+
+// Trains the mice without a window, every mouse forages in its own world
+pub fn mice_headless(mut config: HeadlessConfig, senses: MiceSenses, objective: MiceFitness, arena: Arena) {
+    // Brains are drawn and mutated from the run seed too, so a seed repeats the whole run
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut brains: Vec<Box<dyn Controller>> = (0..POLULATION)
+        .map(|agent| match &config.remote {
+            Some(remote) => remote.controller(agent, 2),
+            None => mice_brain(senses, &mut rng),
+        })
+        .collect();
+    let mut simulated_time = 0.0;
+    let mut champion = None;
+    for epoch in 1..=config.generations {
+        // Every mouse forages in its own seeded world
+        let episodes = evaluate(&mut brains, config.parallel, |index| {
+            MiceEnv::new(HEADLESS_DELTA_TIME, episode_seed(config.seed, epoch, index), senses, objective, arena.clone())
+        });
+        let fitness: Vec<f32> = episodes.iter().map(|&(fitness, _)| fitness).collect();
+        simulated_time += episodes.iter().map(|&(_, time)| time).fold(0.0, f32::max);
+        let mean = fitness.iter().sum::<f32>() / POLULATION as f32;
+        let (best, max_fitness) = fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, &fitness)| (index, fitness))
+            .unwrap();
+        println!("{} *** Fitness: {} Mean: {}", epoch, max_fitness, mean);
+        if let Some(metrics) = config.metrics.as_mut() {
+            let wall_time = metrics.wall_time();
+            metrics.record(&GenerationMetrics::from_fitness(epoch, &fitness, MUTATION, simulated_time, wall_time));
+        }
+
+        let best_brain = brains[best].clone();
+        champion = Some(best_brain.clone());
+        for brain in brains.iter_mut().filter(|brain| brain.evolvable()) {
+            let mut new_brain = best_brain.clone();
+            new_brain.mutate(MUTATION, &mut rng);
+            *brain = new_brain;
+        }
+    }
+    if let (Some(path), Some(mut champion)) = (&config.plot, champion) {
+        let mut env = MiceEnv::new(HEADLESS_DELTA_TIME, episode_seed(config.seed, config.generations + 1, 0), senses, objective, arena);
+        if let Err(error) = mice_plot(&mut env, champion.as_mut()).save(path) {
+            eprintln!("Plot {}: {}", path, error);
+        }
+    }
+}
+
+// Runs one more episode and plots the path over the arena with the food it ate
+fn mice_plot(env: &mut MiceEnv, controller: &mut dyn Controller) -> Figure {
+    controller.reset();
+    let mut observation = env.reset();
+    let food: Vec<Vec2> = env.food.iter().map(|food| food.truncate()).collect();
+    let mut path = vec![env.mice.position.truncate()];
+    let mut eaten = Vec::new();
+    loop {
+        let action = controller.act(&observation);
+        let before = env.food.clone();
+        let (next_observation, _, done) = env.step(&action);
+        path.push(env.mice.position.truncate());
+        // Eaten food respawns elsewhere, its old position is where it was eaten
+        eaten.extend(
+            before
+                .iter()
+                .zip(env.food.iter())
+                .filter(|(before, after)| before != after)
+                .map(|(before, _)| before.truncate()),
+        );
+        observation = next_observation;
+        if done {
+            break;
+        }
+    }
+
+    let mut panel = Panel::new(&format!("Champion path, {} food eaten", eaten.len()), "x", "y");
+    panel.equal_aspect = true;
+    panel.lines.push((env.arena.outline(), [150, 150, 150]));
+    for &(start, end) in env.arena.walls().iter() {
+        panel.lines.push((vec![start, end], [110, 110, 110]));
+    }
+    panel.points.push((food, [230, 200, 160], 1.0));
+    panel.points.push((eaten, [230, 110, 0], 2.5));
+    panel.lines.push((path, [30, 90, 200]));
+    Figure { panels: vec![panel] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_mice_starve_within_a_generation() {
+        let mut mice = Mice::new(MiceSenses::default(), MiceFitness::Survival, Box::new(Idle));
+        let steps = (SIMULATION_TIME / HEADLESS_DELTA_TIME) as usize;
+        for _ in 0..steps {
+            mice_metabolise(&mut mice, 0.0, 0.0, HEADLESS_DELTA_TIME);
+        }
+        assert_eq!(mice.energy, 0.0);
+    }
+
+    #[test]
+    fn ecology_founder_is_the_oldest_evolvable_mouse() {
+        let senses = MiceSenses::default();
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut ecology = Ecology::default();
+        for (age, evolvable) in [(3.0, true), (9.0, true), (5.0, true), (20.0, false)] {
+            let brain = if evolvable { mice_brain(senses, &mut rng) } else { Box::new(Idle) };
+            let mut mice = Mice::new(senses, MiceFitness::Survival, brain);
+            mice.age = age;
+            ecology.remember(&mice);
+        }
+        let founder = ecology.founder.unwrap();
+        assert_eq!(founder.age, 9.0);
+        assert!(founder.brain.evolvable());
+    }
+}
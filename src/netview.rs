@@ -0,0 +1,144 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    color::palettes::css::{DARK_GREY, LIME, RED},
+    ecs::query::Has,
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::controller::*;
+use crate::inspect::*;
+
+// NETWORK VIEW DEFAULTS
+const VIEW_TOGGLE: KeyCode = KeyCode::KeyN;
+const VIEW_SIZE: Vec2 = Vec2::new(320.0, 220.0);
+const VIEW_MARGIN: f32 = 10.0; // Distance from the top right corner of the window
+const NODE_RADIUS: f32 = 4.0; // In screen pixels
+const MAX_NODES: usize = 32; // Larger layers are drawn truncated
+
+// Draws the brain of the selected individual, or of the current leader
+pub struct NetworkViewPlugin<T: Individual>(PhantomData<T>);
+
+impl<T: Individual> Default for NetworkViewPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Individual> Plugin for NetworkViewPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NetworkView { visible: true })
+            .add_systems(Update, (network_view_toggle, network_view::<T>));
+    }
+}
+
+#[derive(Resource)]
+pub struct NetworkView {
+    visible: bool,
+}
+
+fn network_view_toggle(
+    mut view: ResMut<NetworkView>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard.just_pressed(VIEW_TOGGLE) {
+        view.visible = !view.visible;
+    }
+}
+
+fn network_view<T: Individual>(
+    view: Res<NetworkView>,
+    individuals: Query<(&T, Has<Selected>)>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmo: Gizmos,
+) {
+    if !view.visible {
+        return;
+    }
+    let individual = individuals
+        .iter()
+        .find(|(_, selected)| *selected)
+        .or_else(|| individuals.iter().max_by(|a, b| a.0.fitness().total_cmp(&b.0.fitness())))
+        .map(|(individual, _)| individual);
+    let (Some(individual), Ok((camera, camera_transform)), Ok(window)) =
+        (individual, cameras.get_single(), windows.get_single())
+    else {
+        return;
+    };
+    let to_world = |screen: Vec2| camera.viewport_to_world_2d(camera_transform, screen);
+
+    let layout = individual.layout();
+    let observation = individual.observation();
+    let brain = individual.brain();
+    let trace = brain.trace(&observation).unwrap_or_default();
+    let weights = brain.weights();
+
+    // Only input and output are known when the backend hides its hidden layers
+    let activation = |layer: usize| {
+        if trace.len() == layout.len() {
+            trace.get(layer)
+        } else if layer == 0 {
+            trace.first()
+        } else if layer == layout.len() - 1 && trace.len() > 1 {
+            trace.last()
+        } else {
+            None
+        }
+    };
+
+    let origin = Vec2::new(window.width() - VIEW_SIZE.x - VIEW_MARGIN, VIEW_MARGIN);
+    let node = |layer: usize, index: usize| -> Vec2 {
+        let count = layout[layer].min(MAX_NODES);
+        let x = origin.x + VIEW_SIZE.x * layer as f32 / (layout.len() - 1).max(1) as f32;
+        let y = origin.y + VIEW_SIZE.y * (index as f32 + 0.5) / count as f32;
+        Vec2::new(x, y)
+    };
+
+    for layer in 0..layout.len().saturating_sub(1) {
+        for to in 0..layout[layer + 1].min(MAX_NODES) {
+            for from in 0..layout[layer].min(MAX_NODES) {
+                let weight = weights
+                    .as_ref()
+                    .and_then(|weights| weights.get(layer))
+                    .and_then(|layer| layer.get(to))
+                    .and_then(|row| row.get(from))
+                    .copied();
+                let color = match weight {
+                    Some(weight) => activation_color(weight).with_alpha(weight.abs().min(1.0) * 0.6),
+                    None => Color::from(DARK_GREY).with_alpha(0.15),
+                };
+                if let (Some(start), Some(end)) = (to_world(node(layer, from)), to_world(node(layer + 1, to))) {
+                    gizmo.line_2d(start, end, color);
+                }
+            }
+        }
+    }
+
+    // Node radius in world units follows the camera zoom
+    let scale = match (to_world(Vec2::ZERO), to_world(Vec2::X)) {
+        (Some(a), Some(b)) => a.distance(b),
+        _ => 1.0,
+    };
+    for (layer, &size) in layout.iter().enumerate() {
+        for index in 0..size.min(MAX_NODES) {
+            let color = match activation(layer).and_then(|values| values.get(index)) {
+                Some(&value) => activation_color(value).with_alpha(0.3 + 0.7 * value.abs().min(1.0)),
+                None => Color::from(DARK_GREY),
+            };
+            if let Some(center) = to_world(node(layer, index)) {
+                gizmo.circle_2d(center, NODE_RADIUS * scale, color);
+            }
+        }
+    }
+}
+
+// Green for positive, red for negative
+fn activation_color(value: f32) -> Color {
+    if value >= 0.0 {
+        Color::from(LIME)
+    } else {
+        Color::from(RED)
+    }
+}
@@ -0,0 +1,120 @@
+use rand::prelude::*;
+
+use crate::controller::*;
+
+// Fully connected network evolved by mutation. Hidden layers use ReLU and the output tanh,
+// so actions stay between -1 and 1. Weights and hidden activations are exposed to the
+// network view.
+#[derive(Debug, Clone)]
+pub struct Mlp {
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone)]
+struct Layer {
+    weights: Vec<Vec<f32>>, // [to][from]
+    biases: Vec<f32>,
+    scale: f32, // Initial parameter range, mutation steps are relative to it
+}
+
+impl Mlp {
+    // Parameters start uniform within 1/sqrt(inputs) of zero for every layer
+    pub fn new(layout: &[usize], rng: &mut impl Rng) -> Self {
+        let layers = layout
+            .windows(2)
+            .map(|pair| {
+                let scale = 1.0 / (pair[0].max(1) as f32).sqrt();
+                Layer {
+                    weights: (0..pair[1])
+                        .map(|_| (0..pair[0]).map(|_| rng.gen_range(-scale..=scale)).collect())
+                        .collect(),
+                    biases: (0..pair[1]).map(|_| rng.gen_range(-scale..=scale)).collect(),
+                    scale,
+                }
+            })
+            .collect();
+        Self { layers }
+    }
+
+    // Activations of every layer, input first and output last
+    pub fn forward(&self, input: &[f32]) -> Vec<Vec<f32>> {
+        let mut activations = vec![input.to_vec()];
+        for (index, layer) in self.layers.iter().enumerate() {
+            let output = index == self.layers.len() - 1;
+            let previous = activations.last().unwrap();
+            let next = layer
+                .weights
+                .iter()
+                .zip(layer.biases.iter())
+                .map(|(row, bias)| {
+                    let sum = row.iter().zip(previous.iter()).map(|(weight, value)| weight * value).sum::<f32>() + bias;
+                    if output { sum.tanh() } else { sum.max(0.0) }
+                })
+                .collect();
+            activations.push(next);
+        }
+        activations
+    }
+
+    // Moves every weight and bias by up to rate times its layer's initial range either way
+    pub fn perturb(&mut self, rate: f32, rng: &mut (impl Rng + ?Sized)) {
+        for layer in self.layers.iter_mut() {
+            let step = rate.abs() * layer.scale;
+            for value in layer.weights.iter_mut().flatten().chain(layer.biases.iter_mut()) {
+                *value += rng.gen_range(-step..=step);
+            }
+        }
+    }
+}
+
+impl Controller for Mlp {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32> {
+        self.forward(observation).pop().unwrap_or_default()
+    }
+    fn mutate(&mut self, rate: f32, rng: &mut dyn RngCore) {
+        self.perturb(rate, rng);
+    }
+    fn evolvable(&self) -> bool {
+        true
+    }
+    fn name(&self) -> &'static str {
+        "Network"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+    fn trace(&self, observation: &[f32]) -> Option<Vec<Vec<f32>>> {
+        Some(self.forward(observation))
+    }
+    fn weights(&self) -> Option<Vec<Vec<Vec<f32>>>> {
+        Some(self.layers.iter().map(|layer| layer.weights.clone()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_and_weights_follow_the_layout() {
+        let network = Mlp::new(&[3, 5, 2], &mut StdRng::seed_from_u64(1));
+        let trace = network.trace(&[0.2, -0.4, 1.0]).unwrap();
+        assert_eq!(trace.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 5, 2]);
+        assert!(trace[1].iter().all(|&value| value >= 0.0));
+        assert!(trace[2].iter().all(|value| value.abs() <= 1.0));
+        let weights = network.weights().unwrap();
+        assert_eq!((weights.len(), weights[0].len(), weights[0][0].len()), (2, 5, 3));
+        assert_eq!((weights[1].len(), weights[1][0].len()), (2, 5));
+    }
+
+    #[test]
+    fn seeded_networks_repeat() {
+        let build = || {
+            let mut rng = StdRng::seed_from_u64(9);
+            let mut network = Mlp::new(&[4, 6, 1], &mut rng);
+            network.perturb(0.5, &mut rng);
+            network.forward(&[1.0, 0.5, -0.5, 0.0]).pop().unwrap()
+        };
+        assert_eq!(build(), build());
+    }
+}
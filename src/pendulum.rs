@@ -1,18 +1,98 @@
-use bevy::{color::palettes::css::{DARK_GREY, GHOST_WHITE, RED}, prelude::*};
-use astoria_ml::*;
+use bevy::{
+    color::palettes::css::{DARK_GREY, DODGER_BLUE, GHOST_WHITE, ORANGE, RED},
+    prelude::*,
+    window::PrimaryWindow,
+};
+use std::f32::consts::PI;
+
+use rand::prelude::*;
+
+use crate::baseline::*;
+use crate::camera::*;
+use crate::controller::*;
+use crate::env::*;
+use crate::hud::*;
+use crate::metrics::*;
+use crate::network::*;
+use crate::plot::*;
+use crate::remote::*;
+use crate::replay::*;
 
-const CAMERA_SCALE: f32 = 0.5;
 const GRAVITY: f32 = 98.1;
 const NETWORK_LAYOUT: [usize; 7] = [4, 8, 6, 4, 2, 1, 1];
 const RAIL_RADI: f32 = 100.0;
 const CART_SIZE: Vec2 = Vec2::new(10.0, 4.0);
 const PENDULUM_SIZE: Vec2 = Vec2::new(3.0, 3.0);
 const POPULATION: usize = 16;
-const MUTATION: f32 = 10.0;
+const MUTATION: f32 = 0.1; // Fraction of each layer's initial weight range, divided by the generation
 const SIMULATION_TIME: f32 = 10.0;
 const POWER_FACTOR: f32 = 100.0;
 const LENGTH: f32 = 50.0;
 const START_ANGLE: f32 = 180.0_f32;
+const VELOCITY_RANGE: f32 = 100.0;
+const ANGULAR_VELOCITY_RANGE: f32 = 10.0;
+// BASELINE DEFAULTS
+const BASELINES: bool = true; // Run the classic controllers next to the population
+const BASELINE_DELTA_TIME: f32 = 1.0 / 60.0;
+// HEADLESS DEFAULTS
+const HEADLESS_DELTA_TIME: f32 = 1.0 / 60.0;
+// CURRICULUM DEFAULTS
+// Angle PI is upright. Each stage is (start angle spread around upright in degrees,
+// fitness the population's CURRICULUM_QUANTILE needs to move on to the next stage).
+const CURRICULUM: bool = true;
+const CURRICULUM_QUANTILE: f32 = 0.9; // Mutated children drag the average far below what the champion's line can do
+const CURRICULUM_STAGES: [(f32, f32); 5] = [
+    (10.0, 130.0),
+    (30.0, 165.0),
+    (60.0, 180.0),
+    (120.0, 170.0),
+    (180.0, f32::INFINITY),
+];
+// TERMINATION DEFAULTS
+// Terminated carts are frozen and stop gaining fitness until the next generation
+const TERMINATE_FALL_ANGLE: Option<f32> = Some(90.0); // Degrees from upright, only after having been up
+const TERMINATE_STILL_TIME: Option<f32> = Some(3.0); // Seconds without movement while not upright
+const UPRIGHT_ANGLE: f32 = 15.0; // Degrees from upright that count as being up
+const STILL_VELOCITY: f32 = 0.5;
+// DISTURBANCE DEFAULTS
+// Shift + left drag from a bob or cart pushes it, G blows a gust at every cart
+const DRAG_IMPULSE: f32 = 1.0; // Velocity change per world unit dragged
+const DRAG_RADIUS: f32 = 10.0; // World units around a bob or cart that start a drag
+const GUST_KEY: KeyCode = KeyCode::KeyG;
+const GUST_STRENGTH: f32 = 50.0; // Largest horizontal impulse on a bob
+// RAIL DEFAULTS
+const RAIL_MODE: RailMode = RailMode::Terminate;
+const RAIL_RESTITUTION: f32 = 0.8; // Fraction of speed kept when bouncing
+const RAIL_PENALTY: f32 = 10.0; // Fitness lost per rail collision
+
+// What happens when the cart reaches the end of the rail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RailMode {
+    Stop,      // Inelastic stop at the rail end
+    Bounce,    // Elastic bounce, scaled by RAIL_RESTITUTION
+    Terminate, // Stop and end the episode
+}
+
+// Classic controllers that run next to the population but are never selected
+fn baseline_brains() -> Vec<(Box<dyn Controller>, Color)> {
+    let model = CartPoleModel {
+        gravity: GRAVITY,
+        length: LENGTH,
+        power: POWER_FACTOR,
+        rail: RAIL_RADI,
+        velocity_range: VELOCITY_RANGE,
+        angular_velocity_range: ANGULAR_VELOCITY_RANGE,
+        delta_time: BASELINE_DELTA_TIME,
+    };
+    let lqr: Box<dyn Controller> = Box::new(Lqr::new(model));
+    let swing_up: Box<dyn Controller> = Box::new(SwingUp::new(model));
+    let pid: Box<dyn Controller> = Box::new(Pid::new(model));
+    vec![
+        (lqr, Color::from(RED)),
+        (swing_up, Color::from(DODGER_BLUE)),
+        (pid, Color::from(ORANGE)),
+    ]
+}
 
 #[derive(Component, Debug, Clone)]
 pub struct PendulumCart {
@@ -22,10 +102,15 @@ pub struct PendulumCart {
     cart_velocity: Vec3,
     length: f32,
     gravity: f32,
-    brain: Network,
+    brain: Box<dyn Controller>,
+    baseline: bool, // Reference controller, scored but never part of the population
     fitness: f32,
     offset: Vec2, // New field for 
     color: Color,
+    terminated: bool,
+    been_up: bool,
+    still_time: f32,
+    lineage: Lineage,
 }
 
 #[derive(Component)]
@@ -39,32 +124,74 @@ pub struct Generation {
     epoch: usize,
     max_fitness: f32,
     average_fitness: f32,
+    simulated_time: f32,
 }
 
 #[derive(Resource)]
 pub struct GenerationTimer(Timer);
 
+#[derive(Resource)]
+pub struct Curriculum {
+    stage: usize,
+}
+
+impl Curriculum {
+    // Random start angle within the current stage's spread around upright
+    fn start_angle(&self, rng: &mut impl Rng) -> f32 {
+        if !CURRICULUM {
+            return START_ANGLE.to_radians();
+        }
+        let spread = CURRICULUM_STAGES[self.stage].0;
+        let deviation = rng.gen_range(-spread..=spread);
+        (180.0 + deviation).to_radians()
+    }
+    // Move to the next stage once the top of the population clears the current threshold
+    fn advance(&mut self, fitness: &[f32]) -> bool {
+        if !CURRICULUM || self.stage + 1 >= CURRICULUM_STAGES.len() || fitness.is_empty() {
+            return false;
+        }
+        let mut sorted = fitness.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let quantile = sorted[((sorted.len() - 1) as f32 * CURRICULUM_QUANTILE).round() as usize];
+        if quantile >= CURRICULUM_STAGES[self.stage].1 {
+            self.stage += 1;
+            return true;
+        }
+        false
+    }
+}
+
 
 impl PendulumCart {
     fn new(
         length: f32,
         gravity: f32,
         offset: Vec2,
+        start_angle: f32,
+        brain: Box<dyn Controller>,
     ) -> Self {
         Self {
-            angle: START_ANGLE.to_radians(),
+            angle: start_angle,
             angular_velocity: 0.0,
             cart_position: Vec3::new(0.0, 0.0, 1.0),
             cart_velocity: Vec3::new(0.0, 0.0, 1.0),
             length,
             gravity,
-            brain: Network::new(NETWORK_LAYOUT.to_vec(), ActivationFunction::ReLU, ActivationFunction::Tanh),
+            brain,
+            baseline: false,
             fitness: 0.0,
             offset,
-            color: Color::srgba(1.0, 1.0, 1.0, 0.05)
+            color: Color::srgba(1.0, 1.0, 1.0, 0.05),
+            terminated: false,
+            been_up: false,
+            still_time: 0.0,
+            lineage: Lineage::new(),
         }
     }
     fn update(&mut self, delta_time: f32) {
+        if self.terminated {
+            return;
+        }
         let angular_acceleration = (-self.gravity / self.length) * self.angle.sin()
             - (self.cart_velocity.x / self.length) * self.angle.cos();
         self.angular_velocity += angular_acceleration * delta_time;
@@ -78,17 +205,71 @@ impl PendulumCart {
             self.angle += 2.0 * std::f32::consts::PI;
         }
         
-        // Bind the cart to rail
-        if self.cart_position.x < -RAIL_RADI {
-            self.cart_position.x = -RAIL_RADI;
-            self.cart_velocity.x = 0.0; // Stop the cart if it reaches the minimum bound
-        } else if self.cart_position.x > RAIL_RADI {
-            self.cart_position.x = RAIL_RADI;
-            self.cart_velocity.x = 0.0; // Stop the cart if it reaches the maximum bound
-        }
         self.angular_velocity *= 0.999;
         self.cart_position += self.cart_velocity * delta_time;
+
+        // Bind the cart to rail after integrating so it never rests past the ends
+        let hit_rail = self.resolve_rail();
         self.fitness += normalize_to_range(self.angle.to_degrees(), -180.0, 180.0).abs() * (1.0 / (self.cart_position.x.abs()  + 1.0));
+        self.check_termination(hit_rail, delta_time);
+    }
+    // Clamp the cart to the rail and handle the collision, returns true on impact
+    fn resolve_rail(&mut self) -> bool {
+        if self.cart_position.x.abs() <= RAIL_RADI {
+            return false;
+        }
+        let side = self.cart_position.x.signum();
+        self.cart_position.x = side * RAIL_RADI;
+
+        // Only motion heading into the rail end counts as an impact
+        let old_velocity = self.cart_velocity.x;
+        if old_velocity * side <= 0.0 {
+            return false;
+        }
+        let new_velocity = match RAIL_MODE {
+            RailMode::Bounce => -old_velocity * RAIL_RESTITUTION,
+            RailMode::Stop | RailMode::Terminate => 0.0,
+        };
+        self.cart_velocity.x = new_velocity;
+
+        // Transfer the collision impulse to the pole
+        let impulse = new_velocity - old_velocity;
+        self.angular_velocity -= (impulse / self.length) * self.angle.cos();
+        self.fitness -= RAIL_PENALTY;
+        true
+    }
+    // Degrees between the pole and upright
+    fn upright_deviation(&self) -> f32 {
+        180.0 - self.angle.to_degrees().abs()
+    }
+    fn check_termination(&mut self, hit_rail: bool, delta_time: f32) {
+        let deviation = self.upright_deviation();
+        let upright = deviation <= UPRIGHT_ANGLE;
+        if upright {
+            self.been_up = true;
+        }
+
+        let still = self.cart_velocity.x.abs() < STILL_VELOCITY
+            && self.angular_velocity.abs() < STILL_VELOCITY;
+        if still && !upright {
+            self.still_time += delta_time;
+        } else {
+            self.still_time = 0.0;
+        }
+
+        if RAIL_MODE == RailMode::Terminate && hit_rail {
+            self.terminated = true;
+        }
+        if let Some(fall_angle) = TERMINATE_FALL_ANGLE {
+            if self.been_up && deviation > fall_angle {
+                self.terminated = true;
+            }
+        }
+        if let Some(still_time) = TERMINATE_STILL_TIME {
+            if self.still_time >= still_time {
+                self.terminated = true;
+            }
+        }
     }
     fn pendulum_position(
         &self,
@@ -102,109 +283,258 @@ impl PendulumCart {
     }
     fn reset(
         &mut self,
+        start_angle: f32,
     ) {
-        self.angle = START_ANGLE.to_radians();
+        self.angle = start_angle;
         self.angular_velocity = 0.0;
         self.cart_position = Vec3::new(0.0, 0.0, 1.0);
         self.cart_velocity = Vec3::new(0.0, 0.0, 1.0);
         self.fitness = 0.0;
+        self.terminated = false;
+        self.been_up = false;
+        self.still_time = 0.0;
+        self.brain.reset();
+    }
+    // Impulse on the bob, only the part along its swing changes the angle
+    fn push_bob(&mut self, impulse: Vec2) {
+        let tangent = Vec2::new(self.angle.cos(), self.angle.sin());
+        self.angular_velocity += impulse.dot(tangent) / self.length;
+    }
+    fn push_cart(&mut self, impulse: f32) {
+        self.cart_velocity.x += impulse;
+    }
+    fn bob_position(&self) -> Vec2 {
+        self.pendulum_position().truncate() + self.offset
+    }
+    fn apply_action(&mut self, outputs: &[f32], delta_time: f32) {
+        self.cart_velocity.x += outputs[0] * delta_time * POWER_FACTOR;
+    }
+    // Normalized network inputs: angle, cart position, cart velocity, angular velocity
+    fn observation(&self) -> Vec<f32> {
+        vec![
+            normalize_to_range(self.angle.to_degrees(), -180.0, 180.0),
+            normalize_to_range(self.cart_position.x, -RAIL_RADI, RAIL_RADI),
+            normalize_to_range(self.cart_velocity.x, -VELOCITY_RANGE, VELOCITY_RANGE),
+            normalize_to_range(self.angular_velocity, -ANGULAR_VELOCITY_RANGE, ANGULAR_VELOCITY_RANGE),
+        ]
     }
 }
 
-pub fn camera_zoomies(
-    mut query: Query<&mut OrthographicProjection, With<Camera>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-) {
-    for mut projection in query.iter_mut() {
-        projection.viewport_origin = Vec2::new(0.0, 0.0);
-        if keyboard.just_pressed(KeyCode::Equal) {
-            projection.scale += 0.1;
-            println!("Camera scale: {}", projection.scale);
+impl Individual for PendulumCart {
+    fn brain(&self) -> &dyn Controller {
+        self.brain.as_ref()
+    }
+    fn observation(&self) -> Vec<f32> {
+        PendulumCart::observation(self)
+    }
+    fn layout(&self) -> Vec<usize> {
+        if self.brain.evolvable() {
+            NETWORK_LAYOUT.to_vec()
+        } else {
+            vec![4, 1]
         }
-        if keyboard.just_pressed(KeyCode::Minus) {
-            projection.scale -= 0.1;
-            println!("Camera scale: {}", projection.scale);
+    }
+    fn fitness(&self) -> f32 {
+        self.fitness
+    }
+    fn position(&self) -> Vec2 {
+        self.cart_position.truncate() + self.offset
+    }
+    fn describe(&self) -> String {
+        format!(
+            "Brain: {}\nAngle: {:.1}\nAngular velocity: {:.2}\nCart position: {:.1}\nCart velocity: {:.1}\nTerminated: {}",
+            self.brain.name(),
+            self.angle.to_degrees(),
+            self.angular_velocity,
+            self.cart_position.x,
+            self.cart_velocity.x,
+            self.terminated,
+        )
+    }
+    fn lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+}
+
+// Gym style cart-pole episode that runs without Bevy
+pub struct CartPoleEnv {
+    cart: PendulumCart,
+    start_angle: f32,
+    delta_time: f32,
+    time: f32,
+    disturbances: Vec<Disturbance>,
+}
+
+impl CartPoleEnv {
+    pub fn new(delta_time: f32) -> Self {
+        Self {
+            cart: PendulumCart::new(LENGTH, GRAVITY, Vec2::ZERO, START_ANGLE.to_radians(), Box::new(Idle)),
+            start_angle: START_ANGLE.to_radians(),
+            delta_time,
+            time: 0.0,
+            disturbances: Vec::new(),
         }
+    }
+    // Pushes applied during every following episode
+    pub fn set_disturbances(&mut self, disturbances: Vec<Disturbance>) {
+        self.disturbances = disturbances;
+    }
+    // Start angle used by the next reset
+    pub fn set_start_angle(&mut self, start_angle: f32) {
+        self.start_angle = start_angle;
+    }
+}
 
+impl Environment for CartPoleEnv {
+    fn reset(&mut self) -> Vec<f32> {
+        self.cart.reset(self.start_angle);
+        self.time = 0.0;
+        self.cart.observation()
+    }
+    fn step(&mut self, action: &[f32]) -> (Vec<f32>, f32, bool) {
+        let fitness = self.cart.fitness;
+        for disturbance in self.disturbances.iter() {
+            if disturbance.time >= self.time && disturbance.time < self.time + self.delta_time {
+                self.cart.push_bob(Vec2::new(disturbance.bob, 0.0));
+                self.cart.push_cart(disturbance.cart);
+            }
+        }
+        self.cart.apply_action(action, self.delta_time);
+        self.cart.update(self.delta_time);
+        self.time += self.delta_time;
+        let done = self.cart.terminated || self.time >= SIMULATION_TIME;
+        (self.cart.observation(), self.cart.fitness - fitness, done)
+    }
+    fn time(&self) -> f32 {
+        self.time
     }
 }
 
 pub fn pendulum_setup(
     mut commands: Commands, 
+    remote: Option<Res<RemoteLink>>,
 ) {
-    commands.spawn(Camera2dBundle {
-        projection: OrthographicProjection {
-            scale: CAMERA_SCALE, // Zoom out (values less than 1.0 zoom out, values greater than 1.0 zoom in)
-            near: -1000.0, // Ensure it encompasses your z-range
-            far: 1000.0,   // Ensure it encompasses your z-range
-            ..Default::default()
-        },
-        ..Default::default()
-    });
     commands.insert_resource(GenerationTimer(Timer::from_seconds(SIMULATION_TIME, TimerMode::Repeating)));
     commands.insert_resource(Generation{
         epoch: 0,
         max_fitness: 0.0,
         average_fitness: 0.0,
+        simulated_time: 0.0,
     });
+    let curriculum = Curriculum { stage: 0 };
 
     let shift = 200.0;
+    let mut rng = rand::thread_rng();
+    commands.insert_resource(CameraBounds(Rect::from_center_half_size(
+        Vec2::splat(shift),
+        Vec2::new(RAIL_RADI + CART_SIZE.x, LENGTH + PENDULUM_SIZE.y),
+    )));
     for i in 0..POPULATION {
         for j in 0..POPULATION {
-            let cart_entity = commands
-            .spawn(SpriteBundle {
-                sprite: Sprite {
-                    color: Color::srgb(1.0, 1.0, 1.0),
-                    custom_size: Some(CART_SIZE), // Cart size
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .id();
-            
-            let pendulum_ball_entity = commands
-            .spawn(SpriteBundle {
-                sprite: Sprite {
-                    color: Color::srgba(1.0, 1.0, 1.0, 0.05),
-                    custom_size: Some(PENDULUM_SIZE), // Pendulum ball size
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .id();
-            
-            commands.entity(cart_entity).insert(PendulumCart::new(LENGTH, GRAVITY, Vec2::new(shift, shift)));
-            commands.entity(cart_entity).insert(PendulumLinks {
-                cart: cart_entity,
-                pendulum_ball: pendulum_ball_entity,
-            });
+            let brain = match &remote {
+                Some(remote) => remote.controller(i * POPULATION + j, 1),
+                None => pendulum_brain(&mut rng),
+            };
+            let pendulum_cart = PendulumCart::new(LENGTH, GRAVITY, Vec2::new(shift, shift), curriculum.start_angle(&mut rng), brain);
+            spawn_pendulum(&mut commands, pendulum_cart);
         }
     }
+    if BASELINES {
+        for (brain, color) in baseline_brains() {
+            let mut pendulum_cart = PendulumCart::new(LENGTH, GRAVITY, Vec2::new(shift, shift), curriculum.start_angle(&mut rng), brain);
+            pendulum_cart.baseline = true;
+            pendulum_cart.color = color;
+            spawn_pendulum(&mut commands, pendulum_cart);
+        }
+    }
+    commands.insert_resource(curriculum);
+}
+
+// Fresh random network for an evolved cart
+fn pendulum_brain(rng: &mut impl Rng) -> Box<dyn Controller> {
+    Box::new(Mlp::new(&NETWORK_LAYOUT, rng))
+}
+
+fn spawn_pendulum(
+    commands: &mut Commands,
+    pendulum_cart: PendulumCart,
+) {
+    let cart_entity = commands
+    .spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::srgb(1.0, 1.0, 1.0),
+            custom_size: Some(CART_SIZE), // Cart size
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .id();
+
+    let pendulum_ball_entity = commands
+    .spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::srgba(1.0, 1.0, 1.0, 0.05),
+            custom_size: Some(PENDULUM_SIZE), // Pendulum ball size
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .id();
+
+    commands.entity(cart_entity).insert(pendulum_cart);
+    commands.entity(cart_entity).insert(PendulumLinks {
+        cart: cart_entity,
+        pendulum_ball: pendulum_ball_entity,
+    });
 }
 
 pub fn update_pendulum(
     mut query: Query<&mut PendulumCart>,
     time: Res<Time>,
 ) {
-    for mut pendulum_cart in query.iter_mut() {
-        pendulum_cart.update(time.delta_seconds());
-    }
+    // Carts don't interact, so they step on every core
+    let delta_time = time.delta_seconds();
+    query.par_iter_mut().for_each(|mut pendulum_cart| {
+        pendulum_cart.update(delta_time);
+    });
 }
 
 pub fn pendulum_network(
     mut query: Query<&mut PendulumCart>,
     time: Res<Time>,
 ) {
-    for mut pendulum_cart in query.iter_mut() {
-        let mut inputs: Vec<f32> = Vec::new();
-        
-        inputs.push(normalize_to_range(pendulum_cart.angle.to_degrees(), -180.0, 180.0));
-        inputs.push(normalize_to_range(pendulum_cart.cart_position.x, -RAIL_RADI, RAIL_RADI));
-        inputs.push(normalize_to_range(pendulum_cart.cart_velocity.x, -100.0, 100.0));
-        inputs.push(normalize_to_range(pendulum_cart.angular_velocity, -10.0, 10.0));
-        let outputs = pendulum_cart.brain.forward(inputs);
-        pendulum_cart.cart_velocity.x += outputs[0] * (time.delta_seconds()) * POWER_FACTOR;
-    }
+    let delta_time = time.delta_seconds();
+    query.par_iter_mut().for_each(|mut pendulum_cart| {
+        if pendulum_cart.terminated {
+            return;
+        }
+        let inputs = pendulum_cart.observation();
+        let outputs = pendulum_cart.brain.act(&inputs);
+        pendulum_cart.apply_action(&outputs, delta_time);
+    });
+}
+
+// Hands the state of every cart to the recorder when --record is given
+pub fn pendulum_record(
+    query: Query<&PendulumCart>,
+    generation: Res<Generation>,
+    recorder: Option<ResMut<Recorder>>,
+    time: Res<Time>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    let poses = query
+        .iter()
+        .map(|pendulum_cart| Pose {
+            id: pendulum_cart.lineage.id,
+            body: pendulum_cart.position(),
+            head: pendulum_cart.bob_position(),
+            fitness: pendulum_cart.fitness,
+            baseline: pendulum_cart.baseline,
+        })
+        .collect();
+    recorder.record(generation.epoch, time.delta_seconds(), poses, &[]);
 }
 
 fn normalize_to_range(value: f32, min: f32, max: f32) -> f32 {
@@ -218,6 +548,65 @@ fn normalize_to_range(value: f32, min: f32, max: f32) -> f32 {
     2.0 * normalized_value - 1.0
 }
 
+// Shift + left drag from a bob or cart and release to push it, G for a gust on every cart
+pub fn pendulum_disturb(
+    mut drag: Local<Option<(Entity, bool, Vec2)>>, // Cart, whether the bob was grabbed, drag start
+    mut query: Query<(Entity, &mut PendulumCart)>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut gizmo: Gizmos,
+) {
+    if keyboard.just_pressed(GUST_KEY) {
+        let mut rng = rand::thread_rng();
+        for (_, mut pendulum_cart) in query.iter_mut() {
+            pendulum_cart.push_bob(Vec2::new(rng.gen_range(-GUST_STRENGTH..GUST_STRENGTH), 0.0));
+        }
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single()) else {
+        return;
+    };
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift && mouse.just_pressed(MouseButton::Left) {
+        // Bobs win over carts at the same distance since they are what usually gets pushed
+        *drag = query
+            .iter()
+            .flat_map(|(entity, pendulum_cart)| {
+                [
+                    (entity, true, pendulum_cart.bob_position()),
+                    (entity, false, pendulum_cart.position()),
+                ]
+            })
+            .map(|(entity, bob, position)| (entity, bob, position, position.distance(cursor)))
+            .filter(|(_, _, _, distance)| *distance < DRAG_RADIUS)
+            .min_by(|a, b| a.3.partial_cmp(&b.3).unwrap())
+            .map(|(entity, bob, position, _)| (entity, bob, position));
+    }
+    let Some((entity, bob, start)) = *drag else {
+        return;
+    };
+    gizmo.line_2d(start, cursor, Color::from(GHOST_WHITE));
+    if mouse.just_released(MouseButton::Left) {
+        if let Ok((_, mut pendulum_cart)) = query.get_mut(entity) {
+            let impulse = (cursor - start) * DRAG_IMPULSE;
+            if bob {
+                pendulum_cart.push_bob(impulse);
+            } else {
+                pendulum_cart.push_cart(impulse.x);
+            }
+        }
+        *drag = None;
+    }
+}
+
 pub fn render_pendulum(
     mut commands: Commands,
     mut query: Query<(&PendulumCart, &PendulumLinks)>,
@@ -252,51 +641,286 @@ pub fn pendulum_generation(
     mut query: Query<(&mut PendulumCart)>,
     mut generation: ResMut<Generation>,
     mut gen_timer: ResMut<GenerationTimer>,
+    mut curriculum: ResMut<Curriculum>,
+    mut metrics: Option<ResMut<MetricsSink>>,
+    mut hud: ResMut<HudStats>,
     time: ResMut<Time>,
 ) {
-    if gen_timer.0.tick(time.delta()).just_finished() {
+    generation.simulated_time += time.delta_seconds();
+    // End the generation early once every evolving cart has been terminated, baselines may balance forever
+    let all_terminated = query.iter().filter(|pendulum| !pendulum.baseline).all(|pendulum| pendulum.terminated);
+    if gen_timer.0.tick(time.delta()).just_finished() || all_terminated {
+        gen_timer.0.reset();
         generation.epoch += 1;
         generation.max_fitness = 0.0;
         generation.average_fitness = 0.0;
         let mut total = 0.0;
 
         // Calculate total fitness for average calculation
-        for pendulum in query.iter_mut() {
-            total += pendulum.fitness;
+        let fitness: Vec<f32> = query
+            .iter()
+            .filter(|pendulum| !pendulum.baseline)
+            .map(|pendulum| pendulum.fitness)
+            .collect();
+        for pendulum_fitness in fitness.iter() {
+            total += pendulum_fitness;
         }
+        let baselines: Vec<(&'static str, f32)> = query
+            .iter()
+            .filter(|pendulum| pendulum.baseline)
+            .map(|pendulum| (pendulum.brain.name(), pendulum.fitness))
+            .collect();
         generation.average_fitness = total / (POPULATION * POPULATION) as f32;
+        if let Some(metrics) = metrics.as_mut() {
+            let wall_time = metrics.wall_time();
+            metrics.record(&GenerationMetrics::from_fitness(
+                generation.epoch,
+                &fitness,
+                MUTATION / (generation.epoch as f32),
+                generation.simulated_time,
+                wall_time,
+            ));
+        }
+        let stage = curriculum.stage;
+        if curriculum.advance(&fitness) {
+            println!(
+                "Curriculum: stage {} reached, start spread {} degrees",
+                curriculum.stage, CURRICULUM_STAGES[curriculum.stage].0
+            );
+        }
 
         // Find the best pendulum
-        if let Some(mut best_pendulum) = query.iter_mut().max_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap()) {
+        if let Some(mut best_pendulum) = query.iter_mut().filter(|pendulum| !pendulum.baseline).max_by(|a, b| a.fitness.total_cmp(&b.fitness)) {
             let best_brain = best_pendulum.brain.clone();
+            let best_lineage = best_pendulum.lineage.clone();
             generation.max_fitness = best_pendulum.fitness;
 
             // Set the color of the best pendulum to opaque
             best_pendulum.color = Color::rgba(1.0, 1.0, 0.0, 1.0); // Example: fully opaque green
 
             // Mutate the rest of the pendulums based on the best one
+            let mut rng = rand::thread_rng();
             for mut pendulum in query.iter_mut() {
+                // Baselines and remote controllers keep their controller
+                if pendulum.baseline || !pendulum.brain.evolvable() {
+                    pendulum.reset(curriculum.start_angle(&mut rng));
+                    continue;
+                }
                 // Skip the best pendulum
                 if pendulum.fitness == generation.max_fitness{
-                    pendulum.reset();
+                    pendulum.reset(curriculum.start_angle(&mut rng));
                     continue;
                 }
 
                 // Mutate and update the pendulum's brain
                 let mut new_brain = best_brain.clone();
-                new_brain.mutate(MUTATION / (generation.epoch as f32));
+                new_brain.mutate(MUTATION / (generation.epoch as f32), &mut rng);
                 pendulum.brain = new_brain;
+                pendulum.lineage = best_lineage.child(generation.epoch);
 
                 // Set the color of the mutated pendulums to nearly transparent
                 pendulum.color = Color::rgba(0.0, 1.0, 0.0, 0.02); // Example: nearly transparent green
 
                 // Reset the pendulum
-                pendulum.reset();
+                pendulum.reset(curriculum.start_angle(&mut rng));
+            }
+        }
+        println!(
+            "Generation: {}, Stage: {}, Average: {} Max: {}",
+            generation.epoch, stage, generation.average_fitness, generation.max_fitness
+        );
+        for (name, fitness) in baselines {
+            println!("    Baseline {}: {}", name, fitness);
+        }
+        hud.record(generation.max_fitness, generation.average_fitness);
+    }
+    hud.epoch = generation.epoch;
+    hud.progress = gen_timer.0.fraction();
+}
+
+// Trains the pendulums without a window, same selection as pendulum_generation.
+// Returns the curriculum stage the run ended on.
+pub fn pendulum_headless(mut config: HeadlessConfig) -> usize {
+    let mut curriculum = Curriculum { stage: 0 };
+    // Brains are drawn and mutated from the run seed too, so a seed repeats the whole run
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut brains: Vec<Box<dyn Controller>> = (0..POPULATION * POPULATION)
+        .map(|agent| match &config.remote {
+            Some(remote) => remote.controller(agent, 1),
+            None => pendulum_brain(&mut rng),
+        })
+        .collect();
+    let mut baselines: Vec<Box<dyn Controller>> = if BASELINES {
+        baseline_brains().into_iter().map(|(brain, _)| brain).collect()
+    } else {
+        Vec::new()
+    };
+    // Each episode gets its own environment and start angle drawn from its own seed
+    let make_env = |curriculum: &Curriculum, epoch: usize, index: usize| {
+        let mut rng = StdRng::seed_from_u64(episode_seed(config.seed, epoch, index));
+        let mut env = CartPoleEnv::new(HEADLESS_DELTA_TIME);
+        env.set_disturbances(config.disturbances.clone());
+        env.set_start_angle(curriculum.start_angle(&mut rng));
+        env
+    };
+
+    let mut simulated_time = 0.0;
+    let mut champion = None;
+    for epoch in 1..=config.generations {
+        let episodes = evaluate(&mut brains, config.parallel, |index| make_env(&curriculum, epoch, index));
+        let fitness: Vec<f32> = episodes.iter().map(|&(fitness, _)| fitness).collect();
+        simulated_time += episodes.iter().map(|&(_, time)| time).fold(0.0, f32::max);
+        // Baselines continue the index so they never share a start angle with the population
+        let baseline_fitness: Vec<(&'static str, f32)> = evaluate(&mut baselines, config.parallel, |index| {
+            make_env(&curriculum, epoch, brains.len() + index)
+        })
+        .into_iter()
+        .zip(baselines.iter())
+        .map(|((fitness, _), brain)| (brain.name(), fitness))
+        .collect();
+
+        let average_fitness = fitness.iter().sum::<f32>() / fitness.len() as f32;
+        let (best, max_fitness) = fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, &fitness)| (index, fitness))
+            .unwrap();
+        if let Some(metrics) = config.metrics.as_mut() {
+            let wall_time = metrics.wall_time();
+            metrics.record(&GenerationMetrics::from_fitness(
+                epoch,
+                &fitness,
+                MUTATION / (epoch as f32),
+                simulated_time,
+                wall_time,
+            ));
+        }
+        let stage = curriculum.stage;
+        if curriculum.advance(&fitness) {
+            println!(
+                "Curriculum: stage {} reached, start spread {} degrees",
+                curriculum.stage, CURRICULUM_STAGES[curriculum.stage].0
+            );
+        }
+
+        // Keep the best brain and refill the rest with its mutations
+        let best_brain = brains[best].clone();
+        champion = Some(best_brain.clone());
+        for (index, brain) in brains.iter_mut().enumerate() {
+            if index == best || !brain.evolvable() {
+                continue;
             }
+            let mut new_brain = best_brain.clone();
+            new_brain.mutate(MUTATION / (epoch as f32), &mut rng);
+            *brain = new_brain;
         }
         println!(
-            "Generation: {}, Average: {} Max: {}",
-            generation.epoch, generation.average_fitness, generation.max_fitness
+            "Generation: {}, Stage: {}, Average: {} Max: {}",
+            epoch, stage, average_fitness, max_fitness
         );
+        for (name, fitness) in baseline_fitness {
+            println!("    Baseline {}: {}", name, fitness);
+        }
     }
-}
\ No newline at end of file
+    if let (Some(path), Some(mut champion)) = (&config.plot, champion) {
+        let mut env = make_env(&curriculum, config.generations + 1, 0);
+        if let Err(error) = pendulum_plot(&mut env, champion.as_mut()).save(path) {
+            eprintln!("Plot {}: {}", path, error);
+        }
+    }
+    curriculum.stage
+}
+
+// Runs one more episode and plots angle and cart position over time and the phase portrait
+fn pendulum_plot(env: &mut CartPoleEnv, controller: &mut dyn Controller) -> Figure {
+    let mut angle = Vec::new();
+    let mut cart = Vec::new();
+    let mut phase = Vec::new();
+    // The angle wraps at upright, unwrap it so the lines don't jump across the plot
+    let mut unwrapped = env.start_angle;
+    let mut previous = env.start_angle;
+    let mut record = |env: &CartPoleEnv| {
+        let step = (env.cart.angle - previous + PI).rem_euclid(2.0 * PI) - PI;
+        unwrapped += step;
+        previous = env.cart.angle;
+        angle.push(Vec2::new(env.time, unwrapped.to_degrees()));
+        cart.push(Vec2::new(env.time, env.cart.cart_position.x));
+        phase.push(Vec2::new(unwrapped.to_degrees(), env.cart.angular_velocity));
+    };
+
+    controller.reset();
+    let mut observation = env.reset();
+    record(env);
+    loop {
+        let action = controller.act(&observation);
+        let (next_observation, _, done) = env.step(&action);
+        record(env);
+        observation = next_observation;
+        if done {
+            break;
+        }
+    }
+
+    let color = [30, 90, 200];
+    let mut angle_panel = Panel::new("Angle", "Time (s)", "Angle (degrees, 180 is upright)");
+    angle_panel.lines.push((angle, color));
+    let mut cart_panel = Panel::new("Cart position", "Time (s)", "Cart x");
+    cart_panel.lines.push((cart, color));
+    let mut phase_panel = Panel::new("Phase portrait", "Angle (degrees)", "Angular velocity (rad/s)");
+    phase_panel.lines.push((phase, color));
+    Figure {
+        panels: vec![angle_panel, cart_panel, phase_panel],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn windowed_selection_survives_nan_fitness() {
+        let mut world = World::new();
+        world.insert_resource(GenerationTimer(Timer::from_seconds(SIMULATION_TIME, TimerMode::Repeating)));
+        world.insert_resource(Generation {
+            epoch: 0,
+            max_fitness: 0.0,
+            average_fitness: 0.0,
+            simulated_time: 0.0,
+        });
+        world.insert_resource(Curriculum { stage: 0 });
+        world.insert_resource(HudStats::default());
+        world.insert_resource(Time::<()>::default());
+        let mut rng = StdRng::seed_from_u64(2);
+        for fitness in [1.0, f32::NAN, 3.0, -f32::NAN] {
+            let mut cart = PendulumCart::new(LENGTH, GRAVITY, Vec2::ZERO, PI, pendulum_brain(&mut rng));
+            cart.fitness = fitness;
+            cart.terminated = true;
+            world.spawn(cart);
+        }
+        // A balancing baseline must not hold the generation open
+        let mut baseline = PendulumCart::new(LENGTH, GRAVITY, Vec2::ZERO, PI, Box::new(Idle));
+        baseline.baseline = true;
+        world.spawn(baseline);
+
+        world.run_system_once(pendulum_generation);
+        assert_eq!(world.resource::<Generation>().epoch, 1);
+        let mut carts = world.query::<&PendulumCart>();
+        assert!(carts.iter(&world).all(|cart| !cart.terminated && cart.fitness == 0.0));
+    }
+
+    #[test]
+    fn seeded_headless_run_leaves_the_first_stage() {
+        let config = HeadlessConfig {
+            generations: 20,
+            remote: None,
+            metrics: None,
+            disturbances: Vec::new(),
+            plot: None,
+            seed: 5,
+            parallel: true,
+        };
+        assert!(pendulum_headless(config) > 0);
+    }
+}
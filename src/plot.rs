@@ -0,0 +1,256 @@
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use bevy::math::{Rect, Vec2};
+
+// PLOT DEFAULTS
+const PANEL_SIZE: Vec2 = Vec2::new(420.0, 320.0); // Pixels per panel, panels are laid out in a row
+const MARGIN: f32 = 48.0; // Room for the title and axis labels around the data
+const LINE_WIDTH: f32 = 1.5;
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const AXIS: [u8; 3] = [90, 90, 90];
+const FONT_SIZE: f32 = 12.0;
+
+// Small plotting layer for the headless trainers: figures render to SVG or to a
+// CPU rasterized PNG, so reports need no window or GPU. PNGs carry no text.
+pub struct Figure {
+    pub panels: Vec<Panel>,
+}
+
+pub struct Panel {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub equal_aspect: bool, // Same scale on both axes, for maps
+    pub lines: Vec<(Vec<Vec2>, [u8; 3])>,
+    pub points: Vec<(Vec<Vec2>, [u8; 3], f32)>, // Points, color and radius in pixels
+}
+
+impl Panel {
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+            equal_aspect: false,
+            lines: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    // Data range with a little padding so lines don't sit on the frame
+    fn bounds(&self) -> Rect {
+        let bounds = self
+            .lines
+            .iter()
+            .flat_map(|(line, _)| line.iter())
+            .chain(self.points.iter().flat_map(|(points, _, _)| points.iter()))
+            .fold(None, |area: Option<Rect>, &point| match area {
+                Some(area) => Some(area.union_point(point)),
+                None => Some(Rect::from_center_size(point, Vec2::ZERO)),
+            })
+            .unwrap_or(Rect::new(0.0, 0.0, 1.0, 1.0));
+        let plot = PANEL_SIZE - 2.0 * MARGIN;
+        let mut size = bounds.size().max(Vec2::splat(f32::EPSILON));
+        if self.equal_aspect {
+            let scale = (size / plot).max_element();
+            size = plot * scale;
+        }
+        Rect::from_center_size(bounds.center(), size * 1.05)
+    }
+
+    // Maps data to pixels inside the panel at `offset`, y down like images
+    fn transform(&self, offset: f32) -> impl Fn(Vec2) -> Vec2 {
+        let bounds = self.bounds();
+        let plot = PANEL_SIZE - 2.0 * MARGIN;
+        move |point: Vec2| {
+            let unit = (point - bounds.min) / bounds.size();
+            Vec2::new(offset + MARGIN + unit.x * plot.x, MARGIN + (1.0 - unit.y) * plot.y)
+        }
+    }
+}
+
+impl Figure {
+    pub fn size(&self) -> (u32, u32) {
+        ((PANEL_SIZE.x * self.panels.len().max(1) as f32) as u32, PANEL_SIZE.y as u32)
+    }
+
+    // Picks the format from the extension, .svg or .png
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => fs::write(path, self.svg()),
+            Some("png") => self.png(path),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "plot file must end in .svg or .png")),
+        }
+    }
+
+    pub fn svg(&self) -> String {
+        let (width, height) = self.size();
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="{FONT_SIZE}">"#
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(BACKGROUND));
+        for (index, panel) in self.panels.iter().enumerate() {
+            let offset = index as f32 * PANEL_SIZE.x;
+            let to_pixel = panel.transform(offset);
+            let bounds = panel.bounds();
+            let left = offset + MARGIN;
+            let right = offset + PANEL_SIZE.x - MARGIN;
+            let bottom = PANEL_SIZE.y - MARGIN;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{left}" y="{MARGIN}" width="{}" height="{}" fill="none" stroke="{}"/>"#,
+                right - left,
+                bottom - MARGIN,
+                hex(AXIS)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle" font-weight="bold">{}</text>"#,
+                (left + right) / 2.0,
+                MARGIN / 2.0,
+                escape(&panel.title)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                (left + right) / 2.0,
+                PANEL_SIZE.y - MARGIN / 4.0,
+                escape(&panel.x_label)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle" transform="rotate(-90 {} {})">{}</text>"#,
+                left - MARGIN / 2.0,
+                PANEL_SIZE.y / 2.0,
+                left - MARGIN / 2.0,
+                PANEL_SIZE.y / 2.0,
+                escape(&panel.y_label)
+            );
+            // Range of each axis at its ends
+            let _ = writeln!(svg, r#"<text x="{left}" y="{}" text-anchor="start">{:.2}</text>"#, bottom + FONT_SIZE, bounds.min.x);
+            let _ = writeln!(svg, r#"<text x="{right}" y="{}" text-anchor="end">{:.2}</text>"#, bottom + FONT_SIZE, bounds.max.x);
+            let _ = writeln!(svg, r#"<text x="{}" y="{bottom}" text-anchor="end">{:.2}</text>"#, left - 4.0, bounds.min.y);
+            let _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end">{:.2}</text>"#, left - 4.0, MARGIN + FONT_SIZE, bounds.max.y);
+
+            for (line, color) in panel.lines.iter() {
+                let points: Vec<String> = line
+                    .iter()
+                    .map(|&point| to_pixel(point))
+                    .map(|pixel| format!("{:.1},{:.1}", pixel.x, pixel.y))
+                    .collect();
+                let _ = writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{LINE_WIDTH}"/>"#,
+                    points.join(" "),
+                    hex(*color)
+                );
+            }
+            for (points, color, radius) in panel.points.iter() {
+                for &point in points.iter() {
+                    let pixel = to_pixel(point);
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{:.1}" cy="{:.1}" r="{radius}" fill="{}"/>"#,
+                        pixel.x,
+                        pixel.y,
+                        hex(*color)
+                    );
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn png(&self, path: &str) -> io::Result<()> {
+        let (width, height) = self.size();
+        let mut canvas = Canvas::new(width, height);
+        for (index, panel) in self.panels.iter().enumerate() {
+            let offset = index as f32 * PANEL_SIZE.x;
+            let to_pixel = panel.transform(offset);
+            let corners = [
+                Vec2::new(offset + MARGIN, MARGIN),
+                Vec2::new(offset + PANEL_SIZE.x - MARGIN, MARGIN),
+                Vec2::new(offset + PANEL_SIZE.x - MARGIN, PANEL_SIZE.y - MARGIN),
+                Vec2::new(offset + MARGIN, PANEL_SIZE.y - MARGIN),
+            ];
+            for side in 0..4 {
+                canvas.line(corners[side], corners[(side + 1) % 4], AXIS);
+            }
+            for (line, color) in panel.lines.iter() {
+                for pair in line.windows(2) {
+                    canvas.line(to_pixel(pair[0]), to_pixel(pair[1]), *color);
+                }
+            }
+            for (points, color, radius) in panel.points.iter() {
+                for &point in points.iter() {
+                    canvas.disc(to_pixel(point), *radius, *color);
+                }
+            }
+        }
+
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let to_io = |error: png::EncodingError| io::Error::other(error.to_string());
+        let mut writer = encoder.write_header().map_err(to_io)?;
+        writer.write_image_data(&canvas.pixels).map_err(to_io)
+    }
+}
+
+// RGB pixel buffer with just enough drawing for plots
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: BACKGROUND.repeat((width * height) as usize),
+        }
+    }
+    fn plot(&mut self, x: i32, y: i32, color: [u8; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let index = 3 * (y as usize * self.width as usize + x as usize);
+        self.pixels[index..index + 3].copy_from_slice(&color);
+    }
+    // Steps one pixel at a time along the longer axis, thickened to LINE_WIDTH
+    fn line(&mut self, start: Vec2, end: Vec2, color: [u8; 3]) {
+        let steps = (end - start).abs().max_element().ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let point = start.lerp(end, step as f32 / steps as f32);
+            self.disc(point, LINE_WIDTH / 2.0, color);
+        }
+    }
+    fn disc(&mut self, center: Vec2, radius: f32, color: [u8; 3]) {
+        let reach = radius.ceil() as i32;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if ((dx * dx + dy * dy) as f32) <= radius * radius + 0.5 {
+                    self.plot(center.x.round() as i32 + dx, center.y.round() as i32 + dy, color);
+                }
+            }
+        }
+    }
+}
+
+fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::*;
+
+// Line delimited JSON protocol for controllers living in another process.
+// The simulation listens and the external process connects, then for every step:
+//   -> {"type":"observation","agent":3,"observation":[0.1,-0.4,0.0,0.2]}
+//   <- {"action":[0.5]}
+// At the start of every episode the simulation also sends, without expecting a reply:
+//   -> {"type":"reset","agent":3}
+// Addresses are "tcp:127.0.0.1:7878", "unix:/tmp/rare.sock" or a bare "host:port".
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message<'a> {
+    Reset { agent: usize },
+    Observation { agent: usize, observation: &'a [f32] },
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    action: Vec<f32>,
+}
+
+pub struct RemoteSession {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: Box<dyn Write + Send>,
+    failed: HashSet<usize>, // Agents whose error was already reported
+}
+
+impl RemoteSession {
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, message)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+    fn receive(&mut self) -> io::Result<Reply> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "remote controller disconnected"));
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+    // Reports the first error of every agent, later ones fall back silently
+    fn report(&mut self, agent: usize, error: io::Error) {
+        if self.failed.insert(agent) {
+            eprintln!("Remote controller {}: {}, acting with zeros from now on", agent, error);
+        }
+    }
+}
+
+// Shared connection to the external controller process
+#[derive(Resource, Clone)]
+pub struct RemoteLink(Arc<Mutex<RemoteSession>>);
+
+impl RemoteLink {
+    // Binds the address and blocks until the external process connects
+    pub fn listen(address: &str) -> io::Result<Self> {
+        println!("Waiting for remote controller on {}", address);
+        let (reader, writer): (Box<dyn Read + Send>, Box<dyn Write + Send>) =
+            if let Some(path) = address.strip_prefix("unix:") {
+                unix_stream(path)?
+            } else {
+                let address = address.strip_prefix("tcp:").unwrap_or(address);
+                let (stream, _) = TcpListener::bind(address)?.accept()?;
+                stream.set_nodelay(true)?;
+                (Box::new(stream.try_clone()?), Box::new(stream))
+            };
+        println!("Remote controller connected");
+        Ok(Self(Arc::new(Mutex::new(RemoteSession {
+            reader: BufReader::new(reader),
+            writer,
+            failed: HashSet::new(),
+        }))))
+    }
+    // Controller for one agent, actions are padded or cut to action_size
+    pub fn controller(&self, agent: usize, action_size: usize) -> Box<dyn Controller> {
+        Box::new(RemoteController {
+            link: self.clone(),
+            agent,
+            action_size,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn unix_stream(path: &str) -> io::Result<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
+    use std::os::unix::net::UnixListener;
+    let _ = std::fs::remove_file(path);
+    let (stream, _) = UnixListener::bind(path)?.accept()?;
+    Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+}
+
+#[cfg(not(unix))]
+fn unix_stream(_path: &str) -> io::Result<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "unix sockets are not available on this platform"))
+}
+
+#[derive(Clone)]
+pub struct RemoteController {
+    link: RemoteLink,
+    agent: usize,
+    action_size: usize,
+}
+
+impl Controller for RemoteController {
+    fn act(&mut self, observation: &[f32]) -> Vec<f32> {
+        let mut session = self.link.0.lock().unwrap();
+        let message = Message::Observation { agent: self.agent, observation };
+        match session.send(&message).and_then(|_| session.receive()) {
+            Ok(mut reply) => {
+                reply.action.resize(self.action_size, 0.0);
+                reply.action
+            }
+            Err(error) => {
+                session.report(self.agent, error);
+                vec![0.0; self.action_size]
+            }
+        }
+    }
+    fn reset(&mut self) {
+        let mut session = self.link.0.lock().unwrap();
+        if let Err(error) = session.send(&Message::Reset { agent: self.agent }) {
+            session.report(self.agent, error);
+        }
+    }
+    fn name(&self) -> &'static str {
+        "Remote"
+    }
+    fn clone_box(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+// Connects to a listening simulation from the external side
+fn connect(address: &str) -> io::Result<(Box<dyn BufRead>, Box<dyn Write>)> {
+    if let Some(path) = address.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            let stream = std::os::unix::net::UnixStream::connect(path)?;
+            return Ok((Box::new(BufReader::new(stream.try_clone()?)), Box::new(stream)));
+        }
+        #[cfg(not(unix))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported, path.to_string()));
+    }
+    let address = address.strip_prefix("tcp:").unwrap_or(address);
+    let stream = TcpStream::connect(address)?;
+    stream.set_nodelay(true)?;
+    Ok((Box::new(BufReader::new(stream.try_clone()?)), Box::new(stream)))
+}
+
+// Minimal external controller for testing the protocol end to end. It answers
+// every observation with the negated observation, the simulation cuts it to size.
+pub fn loopback_client(address: &str) -> io::Result<()> {
+    let (reader, mut writer) = connect(address)?;
+    let mut steps = 0;
+    for line in reader.lines() {
+        let message: serde_json::Value = serde_json::from_str(&line?)?;
+        if message["type"] != "observation" {
+            continue;
+        }
+        let action: Vec<f32> = message["observation"]
+            .as_array()
+            .map(|values| values.iter().filter_map(|value| value.as_f64()).map(|value| -value as f32).collect())
+            .unwrap_or_default();
+        serde_json::to_writer(&mut writer, &serde_json::json!({ "action": action }))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        steps += 1;
+    }
+    println!("Loopback client answered {} observations", steps);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn loopback_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp:{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        // The client retries until the simulation side is listening
+        let client_address = address.clone();
+        let client = thread::spawn(move || loop {
+            match loopback_client(&client_address) {
+                Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => thread::sleep(Duration::from_millis(10)),
+                result => return result,
+            }
+        });
+
+        let link = RemoteLink::listen(&address).unwrap();
+        let mut controller = link.controller(3, 2);
+        controller.reset();
+        assert_eq!(controller.act(&[0.5, -0.25, 1.0]), vec![-0.5, 0.25]);
+        assert_eq!(controller.act(&[1.0]), vec![-1.0, 0.0]);
+
+        // Closing the link ends the client's stream
+        drop(controller);
+        drop(link);
+        client.join().unwrap().unwrap();
+    }
+}
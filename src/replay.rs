@@ -0,0 +1,550 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    color::palettes::css::{DARK_GREY, DARK_ORANGE, YELLOW},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::camera::*;
+
+// REPLAY DEFAULTS
+const MAGIC: &[u8; 4] = b"RARE";
+const VERSION: u32 = 2;
+pub const RECORD_EVERY: usize = 10; // Generations between recordings, each one holds every tick
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 64.0;
+const JUMP_TIME: f32 = 1.0; // Seconds skipped by page up/down
+const PAUSE_TOGGLE: KeyCode = KeyCode::Space;
+const FRAME_BACK: KeyCode = KeyCode::Comma;
+const FRAME_FORWARD: KeyCode = KeyCode::Period;
+const JUMP_BACK: KeyCode = KeyCode::PageUp;
+const JUMP_FORWARD: KeyCode = KeyCode::PageDown;
+const SPEED_DOWN: KeyCode = KeyCode::BracketLeft;
+const SPEED_UP: KeyCode = KeyCode::BracketRight;
+const TIMELINE_MARGIN: f32 = 20.0; // Screen pixels from the bottom and sides, click it to seek
+const TIMELINE_HEIGHT: f32 = 12.0;
+const CART_SIZE: Vec2 = Vec2::new(10.0, 4.0);
+const BOB_RADIUS: f32 = 1.5;
+const MICE_WIDTH: f32 = 1.0; // Half the base of the mouse triangle
+const FOOD_RADIUS: f32 = 2.0;
+const FONT_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayScenario {
+    Pendulum,
+    Mice,
+}
+
+// Two world points that draw an individual: cart and bob, or mouse body and nose
+#[derive(Debug, Clone, Copy)]
+pub struct Pose {
+    pub id: usize, // Lineage id, follows an individual while others are born and die
+    pub body: Vec2,
+    pub head: Vec2,
+    pub fitness: f32,
+    pub baseline: bool, // Baselines are drawn but never the champion
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    poses: Vec<Pose>,
+    food: Vec<(u32, Vec2)>, // Food that moved this tick, by index
+}
+
+// One generation, stored as the per-tick state of every individual
+#[derive(Debug, Clone)]
+pub struct Recording {
+    scenario: ReplayScenario,
+    epoch: usize,
+    tick: f32,
+    food: Vec<Vec2>, // Food at the first frame
+    frames: Vec<Frame>,
+}
+
+impl Recording {
+    // Layout, all little endian: magic, version, scenario, epoch, tick, food count and
+    // positions, frame count, then per frame the food events, the individual count and
+    // id, baseline flag, body, head and fitness of every individual. The count is per
+    // frame because with --ecology mice are born and die during a recording.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&[self.scenario as u8])?;
+        file.write_all(&(self.epoch as u32).to_le_bytes())?;
+        file.write_all(&self.tick.to_le_bytes())?;
+        file.write_all(&(self.food.len() as u32).to_le_bytes())?;
+        for food in self.food.iter() {
+            write_vec2(&mut file, *food)?;
+        }
+        file.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+        for frame in self.frames.iter() {
+            file.write_all(&(frame.food.len() as u32).to_le_bytes())?;
+            for (index, position) in frame.food.iter() {
+                file.write_all(&index.to_le_bytes())?;
+                write_vec2(&mut file, *position)?;
+            }
+            file.write_all(&(frame.poses.len() as u32).to_le_bytes())?;
+            for pose in frame.poses.iter() {
+                file.write_all(&(pose.id as u32).to_le_bytes())?;
+                file.write_all(&[pose.baseline as u8])?;
+                write_vec2(&mut file, pose.body)?;
+                write_vec2(&mut file, pose.head)?;
+                file.write_all(&pose.fitness.to_le_bytes())?;
+            }
+        }
+        file.flush()
+    }
+
+    pub fn read(path: &str) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let mut reader = Reader { bytes: &bytes, position: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a recording"));
+        }
+        if reader.u32()? != VERSION {
+            return Err(invalid("unsupported recording version"));
+        }
+        let scenario = match reader.take(1)?[0] {
+            0 => ReplayScenario::Pendulum,
+            1 => ReplayScenario::Mice,
+            _ => return Err(invalid("unknown scenario")),
+        };
+        let epoch = reader.u32()? as usize;
+        let tick = reader.f32()?;
+        let food = (0..reader.u32()?).map(|_| reader.vec2()).collect::<io::Result<_>>()?;
+        let frame_count = reader.u32()?;
+        let mut frames = Vec::with_capacity(frame_count as usize);
+        for _ in 0..frame_count {
+            let events = reader.u32()?;
+            let food = (0..events)
+                .map(|_| -> io::Result<(u32, Vec2)> { Ok((reader.u32()?, reader.vec2()?)) })
+                .collect::<io::Result<_>>()?;
+            let poses = (0..reader.u32()?)
+                .map(|_| -> io::Result<Pose> {
+                    Ok(Pose {
+                        id: reader.u32()? as usize,
+                        baseline: reader.take(1)?[0] != 0,
+                        body: reader.vec2()?,
+                        head: reader.vec2()?,
+                        fitness: reader.f32()?,
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+            frames.push(Frame { poses, food });
+        }
+        Ok(Self { scenario, epoch, tick, food, frames })
+    }
+
+    // Id of the best non-baseline individual at the end of the recording
+    fn champion(&self) -> Option<usize> {
+        self.frames.last().and_then(|frame| {
+            frame
+                .poses
+                .iter()
+                .filter(|pose| !pose.baseline)
+                .max_by(|a, b| a.fitness.total_cmp(&b.fitness))
+                .map(|pose| pose.id)
+        })
+    }
+
+    // Food positions after the events up to and including a frame
+    fn food_at(&self, frame: usize) -> Vec<Vec2> {
+        let mut food = self.food.clone();
+        for frame in self.frames.iter().take(frame + 1) {
+            apply_food(&mut food, &frame.food);
+        }
+        food
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.poses.iter().flat_map(|pose| [pose.body, pose.head]))
+            .chain(self.food.iter().copied())
+            .fold(None, |area: Option<Rect>, point| match area {
+                Some(area) => Some(area.union_point(point)),
+                None => Some(Rect::from_center_size(point, Vec2::ZERO)),
+            })
+    }
+}
+
+fn apply_food(food: &mut [Vec2], events: &[(u32, Vec2)]) {
+    for &(index, position) in events.iter() {
+        if let Some(food) = food.get_mut(index as usize) {
+            *food = position;
+        }
+    }
+}
+
+fn write_vec2(file: &mut impl Write, value: Vec2) -> io::Result<()> {
+    file.write_all(&value.x.to_le_bytes())?;
+    file.write_all(&value.y.to_le_bytes())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid("recording ends early"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn vec2(&mut self) -> io::Result<Vec2> {
+        Ok(Vec2::new(self.f32()?, self.f32()?))
+    }
+}
+
+// Collects the frames of a generation and writes them out when the next one starts,
+// or when the app exits and drops it. The scenario's record system calls record once
+// per simulation tick.
+#[derive(Resource)]
+pub struct Recorder {
+    directory: PathBuf,
+    scenario: ReplayScenario,
+    every: usize, // Generations between recordings
+    recording: Option<Recording>,
+    epoch: Option<usize>,
+    food: Vec<Vec2>,
+}
+
+impl Recorder {
+    pub fn create(directory: &str, scenario: ReplayScenario, every: usize) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            scenario,
+            every: every.max(1),
+            recording: None,
+            epoch: None,
+            food: Vec::new(),
+        })
+    }
+
+    pub fn record(&mut self, epoch: usize, tick: f32, poses: Vec<Pose>, food: &[Vec2]) {
+        if self.epoch != Some(epoch) {
+            self.finish();
+            self.epoch = Some(epoch);
+            if epoch.is_multiple_of(self.every) {
+                self.recording = Some(Recording {
+                    scenario: self.scenario,
+                    epoch,
+                    tick,
+                    food: food.to_vec(),
+                    frames: Vec::new(),
+                });
+                self.food = food.to_vec();
+            }
+        }
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        let events: Vec<(u32, Vec2)> = food
+            .iter()
+            .zip(self.food.iter())
+            .enumerate()
+            .filter(|(_, (now, before))| now != before)
+            .map(|(index, (now, _))| (index as u32, *now))
+            .collect();
+        apply_food(&mut self.food, &events);
+        recording.frames.push(Frame { poses, food: events });
+    }
+
+    fn finish(&mut self) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        let name = match recording.scenario {
+            ReplayScenario::Pendulum => "pendulum",
+            ReplayScenario::Mice => "mice",
+        };
+        let path = self.directory.join(format!("{}_{:05}.rare", name, recording.epoch));
+        if let Err(error) = recording.write(&path) {
+            eprintln!("Recording {}: {}", path.display(), error);
+        }
+    }
+}
+
+// The last generation has no next one to trigger its write
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+// Plays a recording back without simulating: space pauses, [ and ] change speed,
+// comma and period step a frame, page up/down jump and clicking the timeline seeks
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(CameraControlsPlugin)
+            .add_systems(Startup, replay_setup)
+            .add_systems(Update, (replay_input, replay_advance, replay_render, replay_text).chain());
+    }
+}
+
+#[derive(Resource)]
+pub struct Replay {
+    recording: Recording,
+    champion: Option<usize>, // Id of the pose to highlight
+    time: f32, // Seconds into the recording
+    paused: bool,
+    speed: f32,
+    food: Vec<Vec2>,
+    food_frame: Option<usize>, // Frame the food positions belong to
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            champion: recording.champion(),
+            recording,
+            time: 0.0,
+            paused: false,
+            speed: 1.0,
+            food: Vec::new(),
+            food_frame: None,
+        }
+    }
+    fn duration(&self) -> f32 {
+        self.recording.frames.len().saturating_sub(1) as f32 * self.recording.tick
+    }
+    fn frame(&self) -> usize {
+        ((self.time / self.recording.tick) as usize).min(self.recording.frames.len().saturating_sub(1))
+    }
+}
+
+#[derive(Component)]
+pub struct ReplayText;
+
+fn replay_setup(
+    mut commands: Commands,
+    replay: Res<Replay>,
+) {
+    if let Some(bounds) = replay.recording.bounds() {
+        commands.insert_resource(CameraBounds(bounds));
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ReplayText,
+    ));
+}
+
+fn replay_input(
+    mut replay: ResMut<Replay>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let tick = replay.recording.tick;
+    if keyboard.just_pressed(PAUSE_TOGGLE) {
+        replay.paused = !replay.paused;
+    }
+    if keyboard.just_pressed(SPEED_UP) {
+        replay.speed = (replay.speed * 2.0).min(MAX_SPEED);
+    }
+    if keyboard.just_pressed(SPEED_DOWN) {
+        replay.speed = (replay.speed / 2.0).max(MIN_SPEED);
+    }
+    let mut seek = 0.0;
+    if keyboard.just_pressed(FRAME_BACK) {
+        seek -= tick;
+        replay.paused = true;
+    }
+    if keyboard.just_pressed(FRAME_FORWARD) {
+        seek += tick;
+        replay.paused = true;
+    }
+    if keyboard.just_pressed(JUMP_BACK) {
+        seek -= JUMP_TIME;
+    }
+    if keyboard.just_pressed(JUMP_FORWARD) {
+        seek += JUMP_TIME;
+    }
+    replay.time = (replay.time + seek).clamp(0.0, replay.duration());
+
+    // Holding the left button on the timeline scrubs
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if let Some(cursor) = window.cursor_position().filter(|_| mouse.pressed(MouseButton::Left)) {
+        let top = window.height() - TIMELINE_MARGIN - TIMELINE_HEIGHT;
+        if cursor.y >= top && cursor.y <= top + TIMELINE_HEIGHT {
+            let width = window.width() - 2.0 * TIMELINE_MARGIN;
+            let fraction = ((cursor.x - TIMELINE_MARGIN) / width).clamp(0.0, 1.0);
+            replay.time = fraction * replay.duration();
+        }
+    }
+}
+
+fn replay_advance(
+    mut replay: ResMut<Replay>,
+    time: Res<Time>,
+) {
+    if !replay.paused {
+        let duration = replay.duration();
+        replay.time = (replay.time + time.delta_seconds() * replay.speed).min(duration);
+    }
+    // Food only stores changes, going back replays them from the start
+    let frame = replay.frame();
+    let food_frame = replay.food_frame;
+    match food_frame {
+        Some(food_frame) if food_frame == frame => {}
+        Some(food_frame) if food_frame < frame => {
+            let Replay { recording, food, .. } = &mut *replay;
+            for frame in recording.frames[food_frame + 1..=frame].iter() {
+                apply_food(food, &frame.food);
+            }
+            replay.food_frame = Some(frame);
+        }
+        _ => {
+            replay.food = replay.recording.food_at(frame);
+            replay.food_frame = Some(frame);
+        }
+    }
+}
+
+fn replay_render(
+    replay: Res<Replay>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut gizmo: Gizmos,
+) {
+    let Some(frame) = replay.recording.frames.get(replay.frame()) else {
+        return;
+    };
+    for food in replay.food.iter() {
+        gizmo.circle_2d(*food, FOOD_RADIUS, Color::from(DARK_ORANGE));
+    }
+    for pose in frame.poses.iter() {
+        let color = if Some(pose.id) == replay.champion {
+            Color::from(YELLOW)
+        } else if pose.baseline {
+            Color::WHITE.with_alpha(0.6)
+        } else {
+            Color::WHITE.with_alpha(0.15)
+        };
+        match replay.recording.scenario {
+            ReplayScenario::Pendulum => {
+                gizmo.line_2d(pose.body, pose.head, color);
+                gizmo.rect_2d(pose.body, 0.0, CART_SIZE, color);
+                gizmo.circle_2d(pose.head, BOB_RADIUS, color);
+            }
+            ReplayScenario::Mice => {
+                let side = (pose.head - pose.body).normalize_or_zero().perp() * MICE_WIDTH;
+                gizmo.linestrip_2d([pose.head, pose.body + side, pose.body - side, pose.head], color);
+            }
+        }
+    }
+
+    // Timeline pinned to the bottom of the window
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single()) else {
+        return;
+    };
+    let to_world = |screen: Vec2| camera.viewport_to_world_2d(camera_transform, screen);
+    let y = window.height() - TIMELINE_MARGIN - TIMELINE_HEIGHT / 2.0;
+    let start = Vec2::new(TIMELINE_MARGIN, y);
+    let end = Vec2::new(window.width() - TIMELINE_MARGIN, y);
+    let fraction = replay.time / replay.duration().max(f32::EPSILON);
+    let marker = start.lerp(end, fraction);
+    if let (Some(start), Some(end)) = (to_world(start), to_world(end)) {
+        gizmo.line_2d(start, end, Color::from(DARK_GREY));
+    }
+    if let (Some(top), Some(bottom)) = (
+        to_world(marker - Vec2::Y * TIMELINE_HEIGHT / 2.0),
+        to_world(marker + Vec2::Y * TIMELINE_HEIGHT / 2.0),
+    ) {
+        gizmo.line_2d(top, bottom, Color::from(YELLOW));
+    }
+}
+
+fn replay_text(
+    replay: Res<Replay>,
+    mut query: Query<&mut Text, With<ReplayText>>,
+) {
+    let champion = replay
+        .champion
+        .and_then(|id| replay.recording.frames.get(replay.frame())?.poses.iter().find(|pose| pose.id == id))
+        .map_or(0.0, |pose| pose.fitness);
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Replay of generation {}\nTime: {:.2} / {:.2}s{}\nSpeed: {:.2}x\nChampion fitness: {:.2}",
+            replay.recording.epoch,
+            replay.time,
+            replay.duration(),
+            if replay.paused { " (paused)" } else { "" },
+            replay.speed,
+            champion,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(id: usize, x: f32) -> Pose {
+        Pose {
+            id,
+            body: Vec2::new(x, 0.0),
+            head: Vec2::new(x, 1.0),
+            fitness: x,
+            baseline: false,
+        }
+    }
+
+    #[test]
+    fn dropping_the_recorder_writes_the_last_generation() {
+        let directory = std::env::temp_dir().join(format!("rare_recorder_{}", std::process::id()));
+        let mut recorder = Recorder::create(directory.to_str().unwrap(), ReplayScenario::Mice, 1).unwrap();
+        recorder.record(3, 0.5, vec![pose(7, 1.0), pose(8, 2.0)], &[Vec2::ZERO]);
+        recorder.record(3, 0.5, vec![pose(7, 1.5), pose(8, 2.5)], &[Vec2::ONE]);
+        // Individuals can die mid recording with --ecology
+        recorder.record(3, 0.5, vec![pose(8, 3.0)], &[Vec2::ONE]);
+        drop(recorder);
+
+        let path = directory.join("mice_00003.rare");
+        let recording = Recording::read(path.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!((recording.epoch, recording.frames.len()), (3, 3));
+        assert_eq!(recording.frames[1].poses[1].body, Vec2::new(2.5, 0.0));
+        assert_eq!(recording.frames[2].poses.len(), 1);
+        assert_eq!(recording.food_at(1), vec![Vec2::ONE]);
+        assert_eq!(recording.champion(), Some(8));
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::math::Vec2;
+
+// How a distance sensor's reading falls from 1 at contact to 0 at its range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    Linear,
+    Quadratic, // Mostly reacts to close hits
+    SquareRoot, // Still reacts to far hits
+    Exponential(f32), // Rate, larger values focus on close hits, rescaled to reach 0 at range
+}
+
+impl Falloff {
+    // Reading for a hit at `fraction` of the range, 0 is contact and 1 the end of the range
+    pub fn apply(&self, fraction: f32) -> f32 {
+        let fraction = fraction.clamp(0.0, 1.0);
+        match *self {
+            Falloff::Linear => 1.0 - fraction,
+            Falloff::Quadratic => (1.0 - fraction).powi(2),
+            Falloff::SquareRoot => (1.0 - fraction).sqrt(),
+            Falloff::Exponential(rate) if rate > 0.0 => {
+                let floor = (-rate).exp();
+                ((-rate * fraction).exp() - floor) / (1.0 - floor)
+            }
+            Falloff::Exponential(_) => 1.0 - fraction,
+        }
+    }
+}
+
+// Distance along a ray to the first point of a circle, 0 when the ray starts inside it.
+// `direction` must be normalized.
+pub fn ray_circle(origin: Vec2, direction: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = origin - center;
+    let along = offset.dot(direction);
+    let outside = offset.length_squared() - radius * radius;
+    if outside <= 0.0 {
+        return Some(0.0);
+    }
+    // Starting outside and pointing away
+    if along > 0.0 {
+        return None;
+    }
+    let discriminant = along * along - outside;
+    if discriminant < 0.0 {
+        return None;
+    }
+    Some(-along - discriminant.sqrt())
+}
+
+// Distance along a ray to where it crosses the segment from start to end.
+// `direction` must be normalized.
+pub fn ray_segment(origin: Vec2, direction: Vec2, start: Vec2, end: Vec2) -> Option<f32> {
+    let edge = end - start;
+    let denominator = direction.perp_dot(edge);
+    // Parallel rays never cross
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    let offset = start - origin;
+    let distance = offset.perp_dot(edge) / denominator;
+    let along = offset.perp_dot(direction) / denominator;
+    (distance >= 0.0 && (0.0..=1.0).contains(&along)).then_some(distance)
+}
+
+// Distance sensor with a range and a falloff curve, reads 0 when nothing is in range
+#[derive(Debug, Clone, Copy)]
+pub struct RaySensor {
+    pub range: f32,
+    pub falloff: Falloff,
+}
+
+impl RaySensor {
+    pub fn read(&self, distance: Option<f32>) -> f32 {
+        match distance {
+            Some(distance) if distance <= self.range => self.falloff.apply(distance / self.range),
+            _ => 0.0,
+        }
+    }
+}
+
+// Kinds of object a vision ray can tell apart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SightClass {
+    Food,
+    Wall,
+    Mouse,
+    Predator,
+}
+
+// How the per class hits of one ray become inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SightEncoding {
+    PerClass, // One distance reading per class
+    OneHot,   // Reading of the nearest hit followed by a one-hot of its class
+}
+
+// Multi-channel vision, every ray gives `channels` inputs
+#[derive(Debug, Clone, Copy)]
+pub struct Sight {
+    pub sensor: RaySensor,
+    pub classes: &'static [SightClass],
+    pub encoding: SightEncoding,
+}
+
+impl Sight {
+    pub fn channels(&self) -> usize {
+        match self.encoding {
+            SightEncoding::PerClass => self.classes.len(),
+            SightEncoding::OneHot => 1 + self.classes.len(),
+        }
+    }
+
+    // `nearest` holds the closest hit distance of each class, in the order of `classes`
+    pub fn encode(&self, nearest: &[Option<f32>], inputs: &mut Vec<f32>) {
+        match self.encoding {
+            SightEncoding::PerClass => inputs.extend(nearest.iter().map(|&distance| self.sensor.read(distance))),
+            SightEncoding::OneHot => {
+                let closest = nearest
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(class, distance)| distance.map(|distance| (class, distance)))
+                    .filter(|&(_, distance)| distance <= self.sensor.range)
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                inputs.push(self.sensor.read(closest.map(|(_, distance)| distance)));
+                inputs.extend((0..self.classes.len()).map(|class| {
+                    if closest.map(|(closest, _)| closest) == Some(class) { 1.0 } else { 0.0 }
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    fn all_close(a: &[f32], b: &[f32]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| close(a, b))
+    }
+
+    #[test]
+    fn ray_circle_hits_and_misses() {
+        // Hit from outside
+        assert_eq!(ray_circle(Vec2::new(-5.0, 0.0), Vec2::X, Vec2::ZERO, 1.0), Some(4.0));
+        // Starting inside
+        assert_eq!(ray_circle(Vec2::new(0.5, 0.0), Vec2::X, Vec2::ZERO, 1.0), Some(0.0));
+        // Circle behind the ray
+        assert_eq!(ray_circle(Vec2::new(5.0, 0.0), Vec2::X, Vec2::ZERO, 1.0), None);
+        // Passing beside it
+        assert_eq!(ray_circle(Vec2::new(-5.0, 2.0), Vec2::X, Vec2::ZERO, 1.0), None);
+        // Tangent
+        assert_eq!(ray_circle(Vec2::new(-5.0, 1.0), Vec2::X, Vec2::ZERO, 1.0), Some(5.0));
+    }
+
+    #[test]
+    fn ray_segment_hits_and_misses() {
+        // Parallel
+        assert_eq!(ray_segment(Vec2::ZERO, Vec2::X, Vec2::new(0.0, 1.0), Vec2::new(5.0, 1.0)), None);
+        // Through either endpoint
+        assert_eq!(ray_segment(Vec2::ZERO, Vec2::X, Vec2::new(3.0, 0.0), Vec2::new(3.0, 2.0)), Some(3.0));
+        assert_eq!(ray_segment(Vec2::ZERO, Vec2::X, Vec2::new(3.0, -2.0), Vec2::new(3.0, 0.0)), Some(3.0));
+        // Past the end of the segment
+        assert_eq!(ray_segment(Vec2::ZERO, Vec2::X, Vec2::new(3.0, 1.0), Vec2::new(3.0, 2.0)), None);
+        // Behind the origin
+        assert_eq!(ray_segment(Vec2::ZERO, Vec2::X, Vec2::new(-3.0, -1.0), Vec2::new(-3.0, 1.0)), None);
+    }
+
+    #[test]
+    fn falloffs_span_contact_to_range() {
+        for falloff in [
+            Falloff::Linear,
+            Falloff::Quadratic,
+            Falloff::SquareRoot,
+            Falloff::Exponential(3.0),
+            Falloff::Exponential(0.0),
+        ] {
+            assert!(close(falloff.apply(0.0), 1.0), "{:?} at contact", falloff);
+            assert!(close(falloff.apply(1.0), 0.0), "{:?} at range", falloff);
+        }
+    }
+
+    #[test]
+    fn ray_sensor_reads_zero_out_of_range() {
+        let sensor = RaySensor { range: 10.0, falloff: Falloff::Linear };
+        assert!(close(sensor.read(Some(5.0)), 0.5));
+        assert_eq!(sensor.read(Some(10.0)), 0.0);
+        assert_eq!(sensor.read(Some(11.0)), 0.0);
+        assert_eq!(sensor.read(None), 0.0);
+    }
+
+    #[test]
+    fn sight_encodings_lay_out_channels() {
+        let sensor = RaySensor { range: 10.0, falloff: Falloff::Linear };
+        let classes = &[SightClass::Food, SightClass::Wall, SightClass::Mouse];
+        let nearest = [Some(5.0), None, Some(2.0)];
+
+        let per_class = Sight { sensor, classes, encoding: SightEncoding::PerClass };
+        let mut inputs = vec![9.0];
+        per_class.encode(&nearest, &mut inputs);
+        assert_eq!(per_class.channels(), 3);
+        assert!(all_close(&inputs, &[9.0, 0.5, 0.0, 0.8]));
+
+        let one_hot = Sight { sensor, classes, encoding: SightEncoding::OneHot };
+        let mut inputs = vec![9.0];
+        one_hot.encode(&nearest, &mut inputs);
+        assert_eq!(one_hot.channels(), 4);
+        assert!(all_close(&inputs, &[9.0, 0.8, 0.0, 0.0, 1.0]));
+
+        // Nothing in range reads zero and marks no class
+        let mut inputs = Vec::new();
+        one_hot.encode(&[Some(20.0), None, None], &mut inputs);
+        assert!(all_close(&inputs, &[0.0, 0.0, 0.0, 0.0]));
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::hud::*;
+
+// SIMULATION DEFAULTS
+const TICK: f32 = 1.0 / 60.0; // Physics step in simulated seconds
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 64.0;
+const MAX_SUBSTEPS: usize = 256; // Per frame, drops time instead of spiralling when the machine can't keep up
+const MAX_GENERATION_TICKS: usize = 100_000; // Gives up on a generation step that never finishes
+const PAUSE_TOGGLE: KeyCode = KeyCode::Space;
+const STEP_TICK: KeyCode = KeyCode::Period;
+const STEP_GENERATION: KeyCode = KeyCode::Enter;
+const SPEED_DOWN: KeyCode = KeyCode::BracketLeft;
+const SPEED_UP: KeyCode = KeyCode::BracketRight;
+const FONT_SIZE: f32 = 14.0;
+
+// Scenario systems that advance the simulation go in this schedule, it runs once per physics tick.
+// Rendering stays in Update so it keeps the frame rate whatever the speed.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationTick;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_schedule(SimulationTick)
+            .init_resource::<SimulationControl>()
+            .add_systems(Startup, simulation_setup)
+            .add_systems(Update, (simulation_input, simulation_run, simulation_text).chain());
+    }
+}
+
+#[derive(Resource)]
+pub struct SimulationControl {
+    paused: bool,
+    speed: f32,
+    step: Option<Step>,
+    accumulator: f32,
+    time: Time, // Clock seen by the tick systems, advances by TICK per substep
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+    Tick,
+    Generation,
+}
+
+impl Default for SimulationControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+            step: None,
+            accumulator: 0.0,
+            time: Time::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SimulationText;
+
+fn simulation_setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        SimulationText,
+    ));
+}
+
+fn simulation_input(
+    mut control: ResMut<SimulationControl>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard.just_pressed(PAUSE_TOGGLE) {
+        control.paused = !control.paused;
+        control.accumulator = 0.0;
+    }
+    if keyboard.just_pressed(SPEED_UP) {
+        control.speed = (control.speed * 2.0).min(MAX_SPEED);
+    }
+    if keyboard.just_pressed(SPEED_DOWN) {
+        control.speed = (control.speed / 2.0).max(MIN_SPEED);
+    }
+    // Stepping only makes sense while paused
+    if control.paused {
+        if keyboard.just_pressed(STEP_TICK) {
+            control.step = Some(Step::Tick);
+        }
+        if keyboard.just_pressed(STEP_GENERATION) {
+            control.step = Some(Step::Generation);
+        }
+    }
+}
+
+// Runs as many fixed substeps as the speed asks for this frame
+fn simulation_run(world: &mut World) {
+    let frame_delta = world.resource::<Time<Virtual>>().delta_seconds();
+    let (ticks, until_generation) = {
+        let mut control = world.resource_mut::<SimulationControl>();
+        match control.step.take() {
+            Some(Step::Tick) => (1, false),
+            Some(Step::Generation) => (MAX_GENERATION_TICKS, true),
+            None if control.paused => (0, false),
+            None => {
+                control.accumulator += frame_delta * control.speed;
+                let ticks = (control.accumulator / TICK) as usize;
+                control.accumulator -= ticks as f32 * TICK;
+                if ticks > MAX_SUBSTEPS {
+                    control.accumulator = 0.0;
+                }
+                (ticks.min(MAX_SUBSTEPS), false)
+            }
+        }
+    };
+    if ticks == 0 {
+        return;
+    }
+
+    // Swap the tick clock in for the frame clock while the simulation runs
+    let frame_time = *world.resource::<Time>();
+    let epoch = world.resource::<HudStats>().epoch;
+    for _ in 0..ticks {
+        let mut tick_time = world.resource::<SimulationControl>().time;
+        tick_time.advance_by(std::time::Duration::from_secs_f32(TICK));
+        world.resource_mut::<SimulationControl>().time = tick_time;
+        *world.resource_mut::<Time>() = tick_time;
+        world.run_schedule(SimulationTick);
+        if until_generation && world.resource::<HudStats>().epoch != epoch {
+            break;
+        }
+    }
+    *world.resource_mut::<Time>() = frame_time;
+}
+
+fn simulation_text(
+    control: Res<SimulationControl>,
+    mut query: Query<&mut Text, With<SimulationText>>,
+) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = if control.paused {
+            format!("Paused, {:.2}x ([.] tick, [Enter] generation)", control.speed)
+        } else {
+            format!("Speed: {:.2}x", control.speed)
+        };
+    }
+}